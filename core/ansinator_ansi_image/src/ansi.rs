@@ -3,29 +3,38 @@
 //! This module defines the Ansinator trait for:
 //! + AnsiImage: A general representation of an image in ansi.
 //!
+//! And the Renderer trait, implemented by every convertion mode.
+//!
 //! Also defines and implements methods for:
-//! + AnsiImageResult: A container for the result of a convertion.
+//! + AnsiImageResult: The canvas resulting of a convertion.
 //!
 #![allow(dead_code, unused)]
 
 use crate::error::AnsiImageError;
-
-use image::{DynamicImage, GenericImageView};
+use crate::ascii::DEFAULT_CHAR_SET;
+use crate::canvas::{Canvas, Cell, CellAttributes, CellColor};
+use ansinator_terminal_colors::{AdaptivePalette, ColorDepth, ColorLut, DistanceMetric, Quantizer, TermPalette, Theme};
+use ansinator_image_binarize::{bayer_matrix, Dithering};
+use crate::sgr::SgrEncoder;
+use crate::html::HtmlOptions;
+use crate::svg::SvgOptions;
+use crate::raster::RasterOptions;
+
+use image::{DynamicImage, GenericImageView, GrayImage, RgbImage};
 use image::imageops::FilterType;
-use ansi_term::{ANSIString, ANSIStrings, Style};
+use ansi_term::{ANSIString, ANSIStrings, Color, Style};
 use terminal_size::{terminal_size, Height, Width};
 use std::fs::File;
-use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
 
 
-/// Container for result AnsiImage convertion
-#[derive(Debug)]
-pub struct AnsiImageResult<'a> {
-    pub data: Vec<ANSIString<'a>>,
-}
+/// Result of an AnsiImage convertion
+pub type AnsiImageResult = Canvas;
 
 /// General representation for AnsiImage
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AnsiImage<T, S> {
     pub invert: bool,
     pub bold: bool,
@@ -42,6 +51,21 @@ pub struct AnsiImage<T, S> {
     pub filter: FilterType,
     pub size: (u32, u32),
     pub scale: (u32, u32),
+    pub char_set: String,
+    pub plain: bool,
+    pub color_depth: ColorDepth,
+    pub metric: DistanceMetric,
+    pub theme: Arc<Theme>,
+    /// Lookup tables of the 8, 16 and 256 terminal colors for the metric and theme,
+    /// built on first use and shared by clones
+    pub color_luts: Arc<[OnceLock<ColorLut>; 3]>,
+    pub quantizer: Option<Quantizer>,
+    /// Adaptive palette of the image being converted
+    pub adaptive_palette: Option<Arc<AdaptivePalette>>,
+    /// Dithering of the colors approximated on a palette
+    pub color_dithering: Option<Dithering>,
+    /// Color the background of each cell with the mean of its unset pixels
+    pub mean_background: bool,
     pub color: S,
     pub mode: T,
 }
@@ -54,6 +78,10 @@ pub trait Ansinator {
     fn bold(&self) -> Self;
     fn blink(&self) -> Self;
     fn underline(&self) -> Self;
+    fn plain(&self) -> Self;
+    fn color_depth(&self, depth: ColorDepth) -> Self;
+    fn metric(&self, metric: DistanceMetric) -> Self;
+    fn theme(&self, theme: Theme) -> Self;
 
     fn set_foreground(&self, foreground: (u8,u8,u8) ) -> Self;
     fn set_background(&self, background: (u8,u8,u8) ) -> Self;
@@ -87,6 +115,16 @@ where T: Default + Copy,
                threshold: 127,
                size: (0,0),
                scale: (1,1),
+               char_set: String::from(DEFAULT_CHAR_SET),
+               plain: false,
+               color_depth: ColorDepth::Truecolor,
+               metric: DistanceMetric::Rgb,
+               theme: Arc::default(),
+               color_luts: Arc::default(),
+               quantizer: None,
+               adaptive_palette: None,
+               color_dithering: None,
+               mean_background: false,
                contrast: 0.0,
                brighten: 0, 
                filter: FilterType::Nearest,
@@ -95,19 +133,35 @@ where T: Default + Copy,
 
     /// Sets bold style
     fn bold(&self) -> Self {
-        Self { bold: true, .. *self }
+        Self { bold: true, .. self.clone() }
     }
     /// Sets blink style
     fn blink(&self) -> Self {
-        Self { blink: true, .. *self }
+        Self { blink: true, .. self.clone() }
     }
     /// Sets underline style
     fn underline(&self) -> Self {
-        Self { underline: true, .. *self }
+        Self { underline: true, .. self.clone() }
+    }
+    /// Produce only characters, without colors nor styles
+    fn plain(&self) -> Self {
+        Self { plain: true, .. self.clone() }
+    }
+    /// Limit the colors to the given terminal color capability
+    fn color_depth(&self, depth: ColorDepth) -> Self {
+        Self { color_depth: depth, .. self.clone() }
+    }
+    /// Set the metric used to approximate colors on the terminal palettes
+    fn metric(&self, metric: DistanceMetric) -> Self {
+        Self { metric, color_luts: Arc::default(), .. self.clone() }
+    }
+    /// Set the real colors of the terminal palettes
+    fn theme(&self, theme: Theme) -> Self {
+        Self { theme: Arc::new(theme), color_luts: Arc::default(), .. self.clone() }
     }
     /// Invert image convertion color
     fn invert(&self) -> Self {
-        Self { invert: true, .. *self }
+        Self { invert: true, .. self.clone() }
     }
     /// Reset attributes to a normal state
     fn normal(&self) -> Self {
@@ -118,26 +172,26 @@ where T: Default + Copy,
                 has_foreground: false,
                 has_background: false,
                 has_threshold: false,
-                .. *self 
+                .. self.clone() 
         }
     }
 
     /// Set fixed foreground
     fn set_foreground(&self, foreground: (u8,u8,u8) ) -> Self {
-        Self{ has_foreground: true, foreground, .. *self}
+        Self{ has_foreground: true, foreground, .. self.clone()}
     }
     /// Set fixed background
     fn set_background(&self, background: (u8,u8,u8) ) -> Self {
-        Self{ has_background: true, background, .. *self}
+        Self{ has_background: true, background, .. self.clone()}
     }
 
     /// Set brighten value
     fn brighten(&self, value: i32) -> Self {
-        Self { brighten: value, .. *self }
+        Self { brighten: value, .. self.clone() }
     }
     /// Set contrast value
     fn contrast(&self, value: f32) -> Self {
-        Self { contrast: value, .. *self }
+        Self { contrast: value, .. self.clone() }
     }
 
     /// Set filter for internal image manipulation
@@ -152,7 +206,7 @@ where T: Default + Copy,
             _ => FilterType::Nearest,
         };
 
-        Self { filter, .. *self }
+        Self { filter, .. self.clone() }
     }
 
     /// Set size to terminal size
@@ -170,13 +224,220 @@ where T: Default + Copy,
     }
     /// Set convertion result size
     fn size(&self, x: u32, y: u32) -> Self {
-        Self { size: (x,y), .. *self }
+        Self { size: (x,y), .. self.clone() }
     } 
 
 }
 
+/// Reader of encoded image bytes, implemented by every `Read + Seek` type
+///
+/// Lets [`Renderer::render_reader`] take any reader while keeping `Renderer` object safe.
+pub trait ReadSeek: Read + Seek {}
+
+impl<R: Read + Seek> ReadSeek for R {}
+
+/// Convertion of a decoded image to its ansi representation
+///
+/// Implemented by every convertion mode, so the mode can be picked at runtime
+/// through a `Box<dyn Renderer>`.
+pub trait Renderer {
+    /// Convert a decoded image
+    fn render(&self, image: &DynamicImage) -> Result<AnsiImageResult, AnsiImageError>;
+
+    /// Convert image file
+    fn render_path(&self, image_path: &str) -> Result<AnsiImageResult, AnsiImageError> {
+        let image = open_image(image_path)?;
+        self.render(&image)
+    }
+
+    /// Convert encoded image bytes
+    fn render_bytes(&self, bytes: &[u8]) -> Result<AnsiImageResult, AnsiImageError> {
+        let image = load_image_from_memory(bytes)?;
+        self.render(&image)
+    }
+
+    /// Convert an encoded image read from `reader`
+    fn render_reader(&self, reader: &mut dyn ReadSeek) -> Result<AnsiImageResult, AnsiImageError> {
+        let image = load_image_from_reader(reader)?;
+        self.render(&image)
+    }
+}
+
 impl<T, S> AnsiImage<T, S> {
 
+    /// Get the fixed foreground and background colors
+    pub fn fixed_color(&self) -> (Option<CellColor>, Option<CellColor>) {
+        let (r, g, b) = self.foreground;
+        let (br, bg, bb) = self.background;
+        match (self.has_foreground, self.has_background) {
+            (false, false) => (None, None),
+            (false, true) => (Some(CellColor::Rgb(0,0,0)), Some(CellColor::Rgb(br,bg,bb))),
+            (true, false) => (Some(CellColor::Rgb(r,g,b)), None),
+            (true, true) => (Some(CellColor::Rgb(r,g,b)), Some(CellColor::Rgb(br,bg,bb))),
+        }
+    }
+
+    /// Get the bold, blink and underline attributes
+    pub fn attributes(&self) -> CellAttributes {
+        CellAttributes {
+            bold: self.bold,
+            blink: self.blink,
+            underline: self.underline,
+        }
+    }
+
+    /// Get a cell with the given colors limited to the color depth and the current attributes,
+    /// or only the character if plain
+    pub fn styled_cell(&self, ch: char, fg: Option<CellColor>, bg: Option<CellColor>) -> Cell {
+        if self.plain {
+            Cell::new(ch)
+        } else {
+            let fg = fg.and_then(|color| self.depth_color(color));
+            let bg = bg.and_then(|color| self.depth_color(color));
+            Cell { ch, fg, bg, attrs: self.attributes() }
+        }
+    }
+
+    /// Get the best approximation of a color within the color depth
+    pub fn depth_color(&self, color: CellColor) -> Option<CellColor> {
+        if self.color_depth == ColorDepth::None {
+            return None;
+        }
+
+        match (self.color_depth.palette(), color) {
+            (None, _) => Some(color),
+            (Some(palette), CellColor::Fixed(index)) if palette.contains(index) => Some(color),
+            (Some(palette), _) => {
+                let (r, g, b) = match color {
+                    CellColor::Fixed(index) => self.theme.color(index),
+                    CellColor::Rgb(r, g, b) => (r, g, b),
+                };
+                Some(CellColor::Fixed(self.color_lut(palette).index(r, g, b)))
+            },
+        }
+    }
+
+    /// Get the index of the best approximation of a color on the 256 terminal colors
+    pub fn term_color(&self, r: u8, g: u8, b: u8) -> u8 {
+        self.color_lut(TermPalette::Ansi256).index(r, g, b)
+    }
+
+    /// Get the lookup table of a palette for the metric and theme, building it on first use
+    pub fn color_lut(&self, palette: TermPalette) -> &ColorLut {
+        self.color_luts[palette as usize].get_or_init(|| ColorLut::with_theme(&self.theme, palette, self.metric))
+    }
+
+    /// Get a copy holding the adaptive palette of the pixels of `rgb` when quantizing,
+    /// restricted to the terminal colors of the color depth if `terminal` or asked by the quantizer
+    pub fn with_adaptive_palette(&self, rgb: &RgbImage, terminal: bool) -> Self
+    where T: Clone,
+          S: Clone,
+    {
+        match self.quantizer {
+            None => self.clone(),
+            Some(quantizer) => {
+                let palette = quantizer.quantize(rgb.pixels().map(|pixel| (pixel[0], pixel[1], pixel[2])));
+                let palette =
+                if terminal || quantizer.is_terminal() {
+                    let term_palette = self.color_depth.palette().unwrap_or(TermPalette::Ansi256);
+                    palette.restrict(&self.theme, term_palette, self.metric)
+                } else {
+                    palette
+                };
+
+                Self { adaptive_palette: Some(Arc::new(palette)), .. self.clone() }
+            },
+        }
+    }
+
+    /// Get the closest color of the adaptive palette, `None` if not quantizing
+    pub fn adaptive_color(&self, r: u8, g: u8, b: u8) -> Option<CellColor> {
+        let palette = self.adaptive_palette.as_ref()?;
+        let position = palette.position(r, g, b, self.metric);
+
+        match palette.indices() {
+            Some(indices) => indices.get(position).map(|&index| CellColor::Fixed(index)),
+            None => palette.colors().get(position).map(|&(r, g, b)| CellColor::Rgb(r, g, b)),
+        }
+    }
+
+    /// Get the color a pixel is approximated to, on the adaptive palette, the palette of the
+    /// color depth or the 256 terminal colors if `terminal`, `None` if colors are kept
+    pub fn palette_color(&self, r: u8, g: u8, b: u8, terminal: bool) -> Option<(u8, u8, u8)> {
+        if self.color_depth == ColorDepth::None {
+            return None;
+        }
+        if let Some(palette) = &self.adaptive_palette {
+            return palette.colors().get(palette.position(r, g, b, self.metric)).copied();
+        }
+
+        let palette =
+        match self.color_depth.palette() {
+            Some(palette) => palette,
+            None if terminal => TermPalette::Ansi256,
+            None => return None,
+        };
+        Some(self.theme.color(self.color_lut(palette).index(r, g, b)))
+    }
+
+    /// Replace the pixels of `rgb` by the colors they are approximated to, see [`Self::palette_color`],
+    /// dithering them when set
+    ///
+    /// The error diffusion follows the pixels of the image, so it spreads across neighbouring
+    /// cells and across the upper and lower pixels of the half blocks.
+    pub fn dither_colors(&self, rgb: &mut RgbImage, terminal: bool) {
+        let dithering =
+        match self.color_dithering {
+            Some(dithering) if self.palette_color(0, 0, 0, terminal).is_some() => dithering,
+            _ => return,
+        };
+
+        if let Some((kernel, divisor)) = dithering.kernel() {
+            let (width, height) = (rgb.width() as i32, rgb.height() as i32);
+            let mut values: Vec<[i32; 3]> = rgb.pixels().map(|p| [p[0] as i32, p[1] as i32, p[2] as i32]).collect();
+
+            for y in 0..height {
+                for x in 0..width {
+                    let value = values[(y * width + x) as usize].map(|c| c.clamp(0, 255));
+                    let (r, g, b) = self.palette_color(value[0] as u8, value[1] as u8, value[2] as u8, terminal)
+                                        .unwrap_or_default();
+                    let error = [value[0] - r as i32, value[1] - g as i32, value[2] - b as i32];
+                    rgb.put_pixel(x as u32, y as u32, image::Rgb([r, g, b]));
+
+                    for &(dx, dy, weight) in kernel {
+                        let (nx, ny) = (x + dx, y + dy);
+                        if nx >= 0 && nx < width && ny < height {
+                            let neighbour = &mut values[(ny * width + nx) as usize];
+                            for c in 0..3 {
+                                neighbour[c] += error[c] * weight / divisor;
+                            }
+                        }
+                    }
+                }
+            }
+        } else if let Some(size) = dithering.matrix_size() {
+            let matrix = bayer_matrix(size);
+            let levels = (size * size) as f64;
+
+            /* Offsets as wide as the gap between the levels of each channel of an evenly spread palette */
+            let colors =
+            match (&self.adaptive_palette, self.color_depth.palette()) {
+                (Some(palette), _) => palette.len(),
+                (None, Some(palette)) => palette.size(),
+                (None, None) => TermPalette::Ansi256.size(),
+            };
+            let spread = f64::min(255.0 / ((colors as f64).cbrt() - 1.0).max(0.0), 255.0);
+
+            for (x, y, p) in rgb.enumerate_pixels_mut() {
+                let offset = ((matrix[(y % size * size + x % size) as usize] as f64 + 0.5) / levels - 0.5) * spread;
+                let value = p.0.map(|c| (c as f64 + offset).round().clamp(0.0, 255.0) as u8);
+                let (r, g, b) = self.palette_color(value[0], value[1], value[2], terminal)
+                                    .unwrap_or_default();
+                *p = image::Rgb([r, g, b]);
+            }
+        }
+    }
+
     /// Get the size, accounting aspect ratio of new dimensions
     ///
     /// If image_dimensions = `(0,0)` returns a image_dimensions 
@@ -225,23 +486,241 @@ impl<T, S> AnsiImage<T, S> {
 
 }
 
-impl<'a> AnsiImageResult<'a> {
-    pub fn print(&self) {
-        let a = ANSIStrings(&self.data);
+/// Get the mean color of the set pixels and of the unset pixels of a binarized window
+/// of `size` starting on the top-left coord (x,y)
+///
+/// A window without set (unset) pixels takes the mean of the unset (set) ones for both.
+pub fn window_colors(rgb: &RgbImage, luma: &GrayImage, x: u32, y: u32, size: (u32,u32)) -> ((u8,u8,u8), (u8,u8,u8)) {
+    let mut sums = [[0u32; 3]; 2];
+    let mut counts = [0u32; 2];
+
+    for j in y..y+size.1 {
+        for i in x..x+size.0 {
+            let set = (luma.get_pixel(i, j)[0] == 255) as usize;
+            let pixel = rgb.get_pixel(i, j);
+            for (c, sum) in sums[set].iter_mut().enumerate() {
+                *sum += pixel[c] as u32;
+            }
+            counts[set] += 1;
+        }
+    }
+
+    let mean = |set: usize| {
+        let set = if counts[set] == 0 { 1 - set } else { set };
+        let n = counts[set].max(1);
+        let channel = |c: usize| ((sums[set][c] + n / 2) / n) as u8;
+        (channel(0), channel(1), channel(2))
+    };
+
+    (mean(1), mean(0))
+}
+
+/// Open and decode an image file
+pub fn open_image(image_path: &str) -> Result<DynamicImage, AnsiImageError> {
+    match image::open(image_path) {
+        Ok(image) => Ok(image),
+        Err(e) => Err(AnsiImageError::ImageError(e)),
+    }
+}
+
+/// Decode an image from a buffer of encoded bytes, guessing its format
+pub fn load_image_from_memory(bytes: &[u8]) -> Result<DynamicImage, AnsiImageError> {
+    match image::load_from_memory(bytes) {
+        Ok(image) => Ok(image),
+        Err(e) => Err(AnsiImageError::ImageError(e)),
+    }
+}
+
+/// Decode an image from a reader of encoded bytes, guessing its format
+pub fn load_image_from_reader<R: Read + Seek>(reader: R) -> Result<DynamicImage, AnsiImageError> {
+    let reader = match image::io::Reader::new(BufReader::new(reader)).with_guessed_format() {
+        Ok(r) => r,
+        Err(e) => return Err(AnsiImageError::ReadError(e)),
+    };
+    match reader.decode() {
+        Ok(image) => Ok(image),
+        Err(e) => Err(AnsiImageError::ImageError(e)),
+    }
+}
+
+/// Get the ansi_term color of a cell color
+fn ansi_term_color(color: CellColor) -> Color {
+    match color {
+        CellColor::Rgb(r, g, b) => Color::RGB(r, g, b),
+        CellColor::Fixed(index) => Color::Fixed(index),
+    }
+}
+
+/// Paint every cell of a row with its own style, ending with a newline
+fn paint_row(row: &[Cell]) -> Vec<ANSIString<'static>> {
+    let style_normal = Style::new();
+    let mut data = vec![];
+
+    for cell in row {
+        let mut style = Style::new();
+        style.foreground = cell.fg.map(ansi_term_color);
+        style.background = cell.bg.map(ansi_term_color);
+        style.is_bold = cell.attrs.bold;
+        style.is_blink = cell.attrs.blink;
+        style.is_underline = cell.attrs.underline;
+
+        data.push(style.paint(cell.ch.to_string()));
+    }
+    data.push(style_normal.paint("\n"));
+
+    data
+}
+
+impl AnsiImageResult {
+    /// Paint every cell with its own style, ending each row with a newline
+    pub fn ansi_strings(&self) -> Vec<ANSIString<'static>> {
+        self.rows()
+            .flat_map(paint_row)
+            .collect()
+    }
+
+    /// Write the ansi representation to `writer`, using the shortest escape sequences
+    ///
+    /// Each row is written and flushed on its own, so the whole
+    /// output is never held in memory.
+    pub fn write_to<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), AnsiImageError> {
+        self.encode_to(writer, SgrEncoder::new().shortest())
+    }
+
+    /// Write the ansi representation to `writer` with the given encoder
+    pub fn encode_to<W: Write + ?Sized>(&self, writer: &mut W, encoder: SgrEncoder) -> Result<(), AnsiImageError> {
+        let mut encoder = encoder;
+        let mut buffer = String::new();
+
+        for row in self.rows() {
+            buffer.clear();
+            encoder.encode_row(row, &mut buffer);
+            if let Err(e) = writer.write_all(buffer.as_bytes()) {
+                return Err(AnsiImageError::WriteError(e));
+            }
+            if let Err(e) = writer.flush() {
+                return Err(AnsiImageError::WriteError(e));
+            }
+        }
+
+        /* Leave the terminal with a clean state */
+        buffer.clear();
+        encoder.finish(&mut buffer);
+        match writer.write_all(buffer.as_bytes()) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(AnsiImageError::WriteError(e)),
+        }
+    }
+
+    /// Write only the characters of each row to `writer`, without escape sequences,
+    /// stripping the trailing whitespace of each row if `trim_trailing`
+    pub fn write_plain<W: Write + ?Sized>(&self, writer: &mut W, trim_trailing: bool) -> Result<(), AnsiImageError> {
+        let mut line = String::new();
+
+        for row in self.rows() {
+            line.clear();
+            line.extend(row.iter().map(|cell| cell.ch));
+            if trim_trailing {
+                line.truncate(line.trim_end().len());
+            }
+            line.push('\n');
+
+            if let Err(e) = writer.write_all(line.as_bytes()) {
+                return Err(AnsiImageError::WriteError(e));
+            }
+        }
+
+        match writer.flush() {
+            Ok(_) => Ok(()),
+            Err(e) => Err(AnsiImageError::WriteError(e)),
+        }
+    }
+
+    /// Write the representation in the given format to `writer`
+    pub fn write_as<W: Write>(&self, writer: &mut W, format: &OutputFormat) -> Result<(), AnsiImageError> {
+        match format {
+            OutputFormat::Ansi => self.write_to(writer),
+            OutputFormat::Plain { trim_trailing } => self.write_plain(writer, *trim_trailing),
+            OutputFormat::Html(options) => self.write_html(writer, options),
+            OutputFormat::Svg(options) => self.write_svg(writer, options),
+            OutputFormat::Png(options) => self.write_png(writer, options),
+        }
+    }
 
-        println!("{}", a);
+    /// Write the ansi representation to stdout
+    pub fn print(&self) -> Result<(), AnsiImageError> {
+        self.print_as(&OutputFormat::Ansi)
     }
 
+    /// Write the representation in the given format to stdout
+    pub fn print_as(&self, format: &OutputFormat) -> Result<(), AnsiImageError> {
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+
+        self.write_as(&mut stdout, format)?;
+        if *format != OutputFormat::Ansi {
+            return Ok(());
+        }
+        match writeln!(stdout) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(AnsiImageError::WriteError(e)),
+        }
+    }
+
+    /// Write the representation to the file in `path`,
+    /// selecting the format by the file extension
     pub fn save(&self, path: &str) -> Result<(),AnsiImageError> {
-        //let mut output = File::create(&path).unwrap();
-        //write!(output, "{}", ANSIStrings(&self.data)).unwrap();
-        let mut output = match File::create(&path) {
+        self.save_as(path, &OutputFormat::from_path(path))
+    }
+
+    /// Write the representation in the given format to the file in `path`
+    pub fn save_as(&self, path: &str, format: &OutputFormat) -> Result<(),AnsiImageError> {
+        let output = match File::create(path) {
             Ok(o) => o,
             Err(e) => return Err(AnsiImageError::FileError(e)),
         };
-        match write!(output, "{}", ANSIStrings(&self.data)) {
+        let mut output = BufWriter::new(output);
+
+        self.write_as(&mut output, format)?;
+
+        match output.flush() {
             Ok(_) => Ok(()),
-            Err(e) => return Err(AnsiImageError::WriteError(e)),
+            Err(e) => Err(AnsiImageError::WriteError(e)),
+        }
+    }
+}
+
+/// Format of a saved convertion
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Text with ansi escape sequences
+    #[default]
+    Ansi,
+    /// Text without escape sequences, optionally without trailing whitespace
+    Plain { trim_trailing: bool },
+    /// HTML page or fragment
+    Html(HtmlOptions),
+    /// SVG vector image
+    Svg(SvgOptions),
+    /// PNG raster image
+    Png(RasterOptions),
+}
+
+impl OutputFormat {
+    /// Select the format by the extension of `path`,
+    /// `.html` and `.htm` give a standalone HTML page, `.svg` an SVG image,
+    /// `.png` a PNG image, anything else ansi text
+    pub fn from_path(path: &str) -> Self {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("html") | Some("htm") => Self::Html(HtmlOptions::new().standalone()),
+            Some("svg") => Self::Svg(SvgOptions::new()),
+            Some("png") => Self::Png(RasterOptions::new()),
+            _ => Self::Ansi,
         }
     }
 }
@@ -250,6 +729,224 @@ impl<'a> AnsiImageResult<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ascii::AnsiAscii;
+    use crate::braile::AnsiBraile;
+    use crate::block::AnsiBlock;
+    use crate::uniblock::AnsiUniblock;
+    use ansinator_terminal_colors::TermColor;
+
+    #[test]
+    fn test_dyn_renderer() {
+        let image_path = "../../tests/images/pic1.jpg";
+
+        let renderers: Vec<Box<dyn Renderer>> = vec![
+            Box::new(AnsiAscii::new().gradient().char_set(" .:#").size(40, 20)),
+            Box::new(AnsiBraile::new().otsu_threshold().size(40, 20)),
+            Box::new(AnsiBlock::new().half().size(40, 20)),
+            Box::new(AnsiUniblock::new().threshold(100).size(40, 20)),
+        ];
+
+        let bytes = std::fs::read(image_path).unwrap();
+
+        for renderer in renderers.iter() {
+            let result = renderer.render_path(image_path).unwrap();
+            assert_eq!(result.width, 40);
+            assert_eq!(result.height, 20);
+            assert_eq!(result.cells.len(), 40 * 20);
+
+            assert_eq!(renderer.render_bytes(&bytes).unwrap(), result);
+            assert_eq!(renderer.render_reader(&mut io::Cursor::new(&bytes)).unwrap(), result);
+        }
+    }
+    #[test]
+    fn test_ansi_strings() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.set(0, 0, Cell { ch: 'a', fg: Some(CellColor::Fixed(9)), .. Default::default() });
+        canvas.set(1, 0, Cell::new('b'));
+
+        let output = ANSIStrings(&canvas.ansi_strings()).to_string();
+
+        assert_eq!(output, "\x1b[38;5;9ma\x1b[0mb\n");
+    }
+
+    #[test]
+    fn test_write_to() {
+        let result = AnsiBraile::new()
+                        .otsu_threshold()
+                        .set_foreground((255,0,0))
+                        .size(30, 10)
+                        .convert("../../tests/images/pic1.jpg")
+                        .unwrap();
+
+        let mut buffer: Vec<u8> = vec![];
+        result.write_to(&mut buffer).unwrap();
+
+        let output = String::from_utf8(buffer).unwrap();
+        assert_eq!(output.matches('\n').count(), 10);
+        /* Same glyphs as the ansi_term painting */
+        let glyphs = |s: &str| s.chars().filter(|c| *c as u32 >= 0x2800).collect::<String>();
+        assert_eq!(glyphs(&output), glyphs(&ANSIStrings(&result.ansi_strings()).to_string()));
+    }
+
+    #[test]
+    fn test_shortest_encoding_size() {
+        let (fg, bg) = (Some(CellColor::Rgb(255,120,180)), Some(CellColor::Fixed(4)));
+        let attrs = CellAttributes { bold: true, underline: true, blink: false };
+
+        /* Fixed style over the whole canvas as in braile and uniblock */
+        let mut canvas = Canvas::new(10, 4);
+        canvas.cells.iter_mut()
+            .for_each(|cell| *cell = Cell { ch: '\u{2800}', fg, bg, attrs });
+
+        let painted = ANSIStrings(&canvas.ansi_strings()).to_string();
+        let mut encoded: Vec<u8> = vec![];
+        canvas.write_to(&mut encoded).unwrap();
+
+        assert_eq!(painted.len(), 260);
+        assert_eq!(encoded.len(), 184);
+
+        /* Alternating colors, every cell needs a new sequence */
+        let mut canvas = Canvas::new(10, 4);
+        for (i, cell) in canvas.cells.iter_mut().enumerate() {
+            let fg = if i % 2 == 0 { Some(CellColor::Fixed(1)) } else { None };
+            *cell = Cell { ch: '#', fg, bg: None, attrs };
+        }
+
+        let painted = ANSIStrings(&canvas.ansi_strings()).to_string();
+        let mut encoded: Vec<u8> = vec![];
+        canvas.write_to(&mut encoded).unwrap();
+
+        assert_eq!(painted.len(), 456);
+        assert_eq!(encoded.len(), 252);
+    }
+
+    #[test]
+    fn test_plain() {
+        let renderers: Vec<Box<dyn Renderer>> = vec![
+            Box::new(AnsiAscii::new().pattern_quadrance().bold().true_color().plain().size(40, 20)),
+            Box::new(AnsiBraile::new().otsu_threshold().underline().set_foreground((255,0,0)).plain().size(40, 20)),
+            Box::new(AnsiBlock::new().half().true_color().plain().size(40, 20)),
+            Box::new(AnsiUniblock::new().otsu_threshold().blink().set_background((0,0,255)).plain().size(40, 20)),
+        ];
+
+        for renderer in renderers.iter() {
+            let result = renderer.render_path("../../tests/images/pic1.jpg").unwrap();
+            assert!(result.cells.iter().all(|cell| cell.same_style(&Cell::default())));
+        }
+
+        let mut canvas = Canvas::new(4, 2);
+        canvas.set(0, 0, Cell { ch: 'a', fg: Some(CellColor::Fixed(9)), .. Default::default() });
+        canvas.set(2, 1, Cell::new('b'));
+
+        let mut buffer: Vec<u8> = vec![];
+        canvas.write_plain(&mut buffer, false).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), "a   \n  b \n");
+
+        let mut buffer: Vec<u8> = vec![];
+        canvas.write_plain(&mut buffer, true).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), "a\n  b\n");
+    }
+
+    #[test]
+    fn test_color_depth() {
+        let braile = AnsiBraile::new()
+                        .otsu_threshold()
+                        .bold()
+                        .set_foreground((255,120,180))
+                        .set_background((0,0,255))
+                        .size(20, 10);
+
+        let cell = |depth| braile.color_depth(depth).render_path("../../tests/images/pic1.jpg").unwrap().cells[0];
+
+        let truecolor = cell(ColorDepth::Truecolor);
+        assert_eq!(truecolor.fg, Some(CellColor::Rgb(255,120,180)));
+
+        let ansi256 = cell(ColorDepth::Ansi256);
+        assert_eq!(ansi256.fg, Some(CellColor::Fixed(TermColor::from(255,120,180).index)));
+        assert_eq!(ansi256.bg, Some(CellColor::Fixed(12)));
+
+        let ansi16 = cell(ColorDepth::Ansi16);
+        assert!(matches!(ansi16.fg, Some(CellColor::Fixed(index)) if index < 16));
+        assert_eq!(ansi16.bg, Some(CellColor::Fixed(12)));
+
+        let ansi8 = cell(ColorDepth::Ansi8);
+        assert!(matches!(ansi8.fg, Some(CellColor::Fixed(index)) if index < 8));
+        assert_eq!(ansi8.bg, Some(CellColor::Fixed(4)));
+
+        /* Basic colors use their own escape sequences */
+        let mut buffer: Vec<u8> = vec![];
+        braile.color_depth(ColorDepth::Ansi8)
+            .render_path("../../tests/images/pic1.jpg").unwrap()
+            .write_to(&mut buffer).unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+        assert!(output.starts_with("\x1b[1;44;3"));
+        assert!(!output.contains(";5;"));
+
+        let none = cell(ColorDepth::None);
+        assert_eq!((none.fg, none.bg), (None, None));
+        assert!(none.attrs.bold);
+    }
+
+    #[test]
+    fn test_metric() {
+        /* Orange is olive on 16 colors by RGB, bright red by CIEDE2000 */
+        let braile = AnsiBraile::new()
+                        .otsu_threshold()
+                        .set_foreground((255,128,0))
+                        .size(20, 10)
+                        .color_depth(ColorDepth::Ansi16);
+
+        let cell = |metric| braile.metric(metric).render_path("../../tests/images/pic1.jpg").unwrap().cells[0];
+
+        assert_eq!(cell(DistanceMetric::Rgb).fg, Some(CellColor::Fixed(3)));
+        assert_eq!(cell(DistanceMetric::Ciede2000).fg, Some(CellColor::Fixed(9)));
+    }
+
+    #[test]
+    fn test_color_luts() {
+        let block = AnsiBlock::new().half().terminal_color().size(20, 10);
+        block.render_path("../../tests/images/pic1.jpg").unwrap();
+
+        /* Built on first use and shared by clones */
+        assert!(block.color_luts[TermPalette::Ansi256 as usize].get().is_some());
+        assert!(block.color_luts[TermPalette::Ansi16 as usize].get().is_none());
+        assert!(Arc::ptr_eq(&block.color_luts, &block.bold().color_luts));
+
+        /* A new metric needs new tables */
+        let block = block.metric(DistanceMetric::Redmean);
+        assert!(block.color_luts[TermPalette::Ansi256 as usize].get().is_none());
+        assert_eq!(block.term_color(255,128,0), TermColor::closest(255,128,0, TermPalette::Ansi256, DistanceMetric::Redmean).index);
+    }
+
+    #[test]
+    fn test_theme() {
+        /* A theme whose blue is far from the xterm one */
+        let theme = Theme::from_colors(&[(0,43,54), (220,50,47), (133,153,0), (181,137,0), (38,139,210)]);
+        let braile = AnsiBraile::new()
+                        .otsu_threshold()
+                        .set_foreground((40,140,200))
+                        .size(20, 10)
+                        .color_depth(ColorDepth::Ansi8);
+
+        let cell = |braile: AnsiBraile| braile.render_path("../../tests/images/pic1.jpg").unwrap().cells[0];
+
+        assert_eq!(cell(braile.clone()).fg, Some(CellColor::Fixed(6)));
+        assert_eq!(cell(braile.theme(theme.clone())).fg, Some(CellColor::Fixed(4)));
+        assert!(!Arc::ptr_eq(&braile.color_luts, &braile.theme(theme).color_luts));
+    }
+
+    #[test]
+    fn test_output_format_from_path() {
+        let html = OutputFormat::Html(HtmlOptions::new().standalone());
+
+        assert_eq!(OutputFormat::from_path("out.html"), html);
+        assert_eq!(OutputFormat::from_path("dir/out.HTM"), html);
+        assert_eq!(OutputFormat::from_path("out.svg"), OutputFormat::Svg(SvgOptions::new()));
+        assert_eq!(OutputFormat::from_path("out.png"), OutputFormat::Png(RasterOptions::new()));
+        assert_eq!(OutputFormat::from_path("out.txt"), OutputFormat::Ansi);
+        assert_eq!(OutputFormat::from_path("out"), OutputFormat::Ansi);
+    }
+
     /*
     #[test]
     fn create() {
@@ -2,12 +2,15 @@
 
 #![allow(dead_code, unused)]
 
-use crate::ansi::{AnsiImage, AnsiImageResult, Ansinator};
+use crate::ansi::{self, AnsiImage, AnsiImageResult, Ansinator, Renderer};
+use crate::canvas::{Canvas, Cell, CellColor};
 use crate::error::AnsiImageError;
+use ansinator_image_binarize::Dithering;
+use ansinator_terminal_colors::Quantizer;
 use ansinator_ascii_font::AsciiFont;
 use image::{DynamicImage, GenericImageView, RgbImage, GrayImage};
 use std::default::Default;
-use ansi_term::Color;
+use std::io::{Read, Seek};
 
 /// Ascii coloring method
 #[derive(Debug, Clone, Copy)]
@@ -37,270 +40,265 @@ impl Default for AsciiMode {
    }
 }
 
+/// Default character set, all printable ascii characters
+pub const DEFAULT_CHAR_SET: &str = " !\"#$%&\'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+
 pub type AnsiAscii = AnsiImage<AsciiMode, AsciiColor>;
 
 impl AnsiAscii {
     /// Coloring with true color (RGB8)
     pub fn true_color(&self) -> Self {
-        Self { color: AsciiColor::Truecolor, .. *self}
+        Self { color: AsciiColor::Truecolor, .. self.clone()}
     }
     /// Coloring with terminal colors (256 terminal color)
     pub fn terminal_color(&self) -> Self {
-        Self { color: AsciiColor::Terminalcolor, .. *self}
+        Self { color: AsciiColor::Terminalcolor, .. self.clone()}
     }
     /// Set fixed RGB foreground
     fn set_foreground(&self, foreground: (u8,u8,u8) ) -> Self {
-        Self{ has_foreground: true, foreground, color: AsciiColor::Fixed, .. *self}
+        Self{ has_foreground: true, foreground, color: AsciiColor::Fixed, .. self.clone()}
     }
     /// Set fixed RGB background 
     fn set_background(&self, background: (u8,u8,u8) ) -> Self {
-        Self{ has_background: true, background, color: AsciiColor::Fixed, .. *self}
+        Self{ has_background: true, background, color: AsciiColor::Fixed, .. self.clone()}
+    }
+
+    /// Color with an adaptive palette built for each image
+    pub fn quantize(&self, quantizer: Quantizer) -> Self {
+        Self { quantizer: Some(quantizer), .. self.clone()}
+    }
+    /// Dither the colors approximated on the terminal, color depth or adaptive palette
+    pub fn dither(&self, dithering: Dithering) -> Self {
+        Self { color_dithering: Some(dithering), .. self.clone()}
+    }
+
+    /// Set character set used for convertion
+    pub fn char_set(&self, char_set: &str) -> Self {
+        Self { char_set: String::from(char_set), .. self.clone()}
     }
 
     /// Set unicode gradient convertion mode
     pub fn gradient(&self) -> Self {
-        Self { mode: AsciiMode::Gradient, scale: (1,1), .. *self}
+        Self { mode: AsciiMode::Gradient, scale: (1,1), .. self.clone()}
     }
     /// Set ascii pattern (quadrance metric) convertion mode
     pub fn pattern_quadrance(&self) -> Self {
-        Self { mode: AsciiMode::PatternQuadrance, scale: (5,7), .. *self}
+        Self { mode: AsciiMode::PatternQuadrance, scale: (5,7), .. self.clone()}
     } 
     /// Set ascii pattern (structural similarity) convertion mode
     pub fn pattern_ssim(&self) -> Self {
-        Self { mode: AsciiMode::PatternSsim, scale: (5,7), .. *self}
+        Self { mode: AsciiMode::PatternSsim, scale: (5,7), .. self.clone()}
     } 
 
-    /// get appropiate color for current convertion mode
-    fn get_color(&self, r: u8, g:u8, b:u8) -> ansi_term::Style {
+    /// get appropiate foreground and background colors for current convertion mode
+    fn get_color(&self, r: u8, g:u8, b:u8) -> (Option<CellColor>, Option<CellColor>) {
             match self.color {
+            AsciiColor::Truecolor | AsciiColor::Terminalcolor if self.adaptive_palette.is_some() => {
+               (self.adaptive_color(r,g,b), None)
+            },
             AsciiColor::Truecolor => {
-               Color::RGB(r,g,b).normal()
+               (Some(CellColor::Rgb(r,g,b)), None)
             },
             AsciiColor::Terminalcolor => {
-                let index = ansinator_terminal_colors::TermColor::from(r, g, b)
-                                .index;
-               Color::Fixed(index).normal()
+                let index = self.term_color(r, g, b);
+               (Some(CellColor::Fixed(index)), None)
             },
             AsciiColor::Fixed => {
-                match (self.has_foreground, self.has_background) {
-                    (false, false) => {
-                        ansi_term::Style::new()
-                    },
-                    (false, true) => {
-                        let (br, bg, bb) = self.background;
-                        Color::RGB(0,0,0).on(Color::RGB(br,bg,bb))
-                    },
-                    (true, false) => {
-                        let (r, g, b) = self.foreground;
-                        Color::RGB(r,g,b).normal()
-                    },
-                    (true, true) => {
-                        let (r, g, b) = self.foreground;
-                        let (br, bg, bb) = self.background;
-                        Color::RGB(r,g,b).on(Color::RGB(br,bg,bb))
-                    },
-                }
+                self.fixed_color()
             },
         }
     }
-    /// get appropiate color along style for current convertion mode
-    pub fn get_style(&self, r:u8, g:u8, b:u8) -> ansi_term::Style {
-        let mut style =  self.get_color(r,g,b);
-        if self.bold {
-            style = style.bold()
-        }
-        if self.blink {
-            style = style.blink()
-        }
-        if self.underline {
-            style = style.underline()
-        }
-
-        style
+    /// get cell of a character with appropiate color along style for current convertion mode
+    pub fn get_cell(&self, ch: char, r:u8, g:u8, b:u8) -> Cell {
+        let (fg, bg) = self.get_color(r,g,b);
+        self.styled_cell(ch, fg, bg)
     }
 
     /// Convert image file to ascii representation
-    pub fn convert(&self, image_path: &str, char_set: &str) -> Result<AnsiImageResult, AnsiImageError>{
-
-        /* Try opening the image */
-        let image = match image::open(image_path) {
-            Ok(image) => image,
-            Err(e) => return Err(AnsiImageError::ImageError(e)),
-        };
-
-
-        /* Get requested size of image (without scaling!!) for later */
-        let size = self.size_aspect_ratio(image.dimensions());
-
-        /* Resize image to satisfy all internal parameters */
-        let image = image.adjust_contrast(self.contrast)
-                        .brighten(self.brighten);
-        let mut image = self.image_resize_with_scale(&image);
-
-        /* Invert colors */
-        if self.invert {
-            image.invert();
-        }
-
-        /* Cast to luma with scaled size */
-        let luma = image.to_luma8();
-        /* Cast image to rgb but resizing to keep proportion rgb:luma => (1:1) : (scale.0 : scale.1) 
-         * by utilizing previously compute non scaled size
-         * */ 
-        let rgb = image.resize_exact(size.0, size.1, self.filter)
-                        .to_rgb8();
-
-        assert_eq!(rgb.width() * self.scale.0, luma.width());
-        assert_eq!(rgb.height() * self.scale.1, luma.height());
-
-        let res =
-        match self.mode {
-            AsciiMode::Gradient => {
-                let char_set = char_set.chars()
-                                    .collect::<Vec<char>>();
-
-                self.ascii_gradient(rgb, luma, &char_set)
-            },
-            AsciiMode::PatternQuadrance => {
-                /* Create font set */
-                let mut ascii_font_set = char_set.chars()
-                                      .map(|c| AsciiFont::from(c))
-                                      .collect::<Vec<AsciiFont>>();
-                /* Dedup font set to increase convertion speed */
-                ascii_font_set.sort_unstable();
-                ascii_font_set.dedup();
-
-                self.ascii_pattern_quadrance(rgb, luma, &ascii_font_set)
-            },
-            AsciiMode::PatternSsim => {
-                /* Create font set */
-                let mut ascii_font_set = char_set.chars()
-                                      .map(|c| AsciiFont::from(c))
-                                      .collect::<Vec<AsciiFont>>();
-                /* Dedup font set to increase convertion speed */
-                ascii_font_set.sort_unstable();
-                ascii_font_set.dedup();
+    pub fn convert(&self, image_path: &str, char_set: &str) -> Result<AnsiImageResult, AnsiImageError> {
+        let image = ansi::open_image(image_path)?;
+        self.convert_image(&image, char_set)
+    }
 
-                self.ascii_pattern_ssim(rgb, luma, &ascii_font_set)
-            },
-        };
+    /// Convert encoded image bytes to ascii representation
+    pub fn convert_bytes(&self, bytes: &[u8], char_set: &str) -> Result<AnsiImageResult, AnsiImageError> {
+        let image = ansi::load_image_from_memory(bytes)?;
+        self.convert_image(&image, char_set)
+    }
 
-        Ok(res)
+    /// Convert an encoded image read from `reader` to ascii representation
+    pub fn convert_reader<R: Read + Seek>(&self, reader: R, char_set: &str) -> Result<AnsiImageResult, AnsiImageError> {
+        let image = ansi::load_image_from_reader(reader)?;
+        self.convert_image(&image, char_set)
     }
 
+    /// Convert a decoded image to ascii representation
+    pub fn convert_image(&self, image: &DynamicImage, char_set: &str) -> Result<AnsiImageResult, AnsiImageError> {
+        self.char_set(char_set).render(image)
+    }
 
     /// Convert RGB image to a text representation using ansi (24-bit) true color or 256 terminal colors,
     /// mapping the the pattern (quadrance metric) of a window of luma values to ascii
     /// in a given ascii character set.
-    fn ascii_pattern_quadrance<'b>(&self, rgb: RgbImage, luma: GrayImage, font_set: &Vec<AsciiFont>) -> AnsiImageResult<'b> {
-        /* Create Result */
-        let mut ansi = AnsiImageResult{ data: vec![] };
-
-        /* Create initial style for later modification */
-        let mut style = self.get_style(0,0,0);
-        let style_normal = ansi_term::Style::new();
-
+    fn ascii_pattern_quadrance(&self, rgb: RgbImage, luma: GrayImage, font_set: &Vec<AsciiFont>) -> AnsiImageResult {
         /* Get image dimensions */
         let width = rgb.width();
         let height = rgb.height();
 
+        /* Create Result */
+        let mut canvas = Canvas::new(width, height);
+
         for y in (0..height) {
             for x in (0..width) {
                 /* Get RGB Color */
-                let rgb_pixel = rgb.get_pixel(x+0,y+0);
+                let rgb_pixel = rgb.get_pixel(x,y);
                 let r = rgb_pixel[0];
                 let g = rgb_pixel[1];
                 let b = rgb_pixel[2];
 
-                /* Convert to appropiate color and style */
-                style = self.get_style(r,g,b);
-
                 /* Get window character */
-                let ch = window_analysis_quadrance(&luma, x, y, &font_set)
-                            .to_string();
+                let ch = window_analysis_quadrance(&luma, x, y, font_set);
 
-                /* Add ansi */
-                ansi.data.push(style.paint(ch));
+                /* Convert to appropiate color and style */
+                canvas.set(x, y, self.get_cell(ch, r,g,b));
             }
-            ansi.data.push(style_normal.paint("\n"));
         }
        
-        ansi
+        canvas
     }
 
     /// Convert RGB image to a text representation using ansi (24-bit) true color or 256 terminal colors,
     /// mapping the the pattern (structural similarity metric) of a window of luma values to ascii
     /// in a given ascii character set.
-    fn ascii_pattern_ssim<'b>(&self, rgb: RgbImage, luma: GrayImage, font_set: &Vec<AsciiFont>) -> AnsiImageResult<'b> {
-        /* Create Result */
-        let mut ansi = AnsiImageResult{ data: vec![] };
-
-        /* Create initial style for later modification */
-        let mut style = self.get_style(0,0,0);
-        let style_normal = ansi_term::Style::new();
-
+    fn ascii_pattern_ssim(&self, rgb: RgbImage, luma: GrayImage, font_set: &Vec<AsciiFont>) -> AnsiImageResult {
         /* Get image dimensions */
         let width = rgb.width();
         let height = rgb.height();
 
+        /* Create Result */
+        let mut canvas = Canvas::new(width, height);
+
         for y in (0..height) {
             for x in (0..width) {
                 /* Get RGB Color */
-                let rgb_pixel = rgb.get_pixel(x+0,y+0);
+                let rgb_pixel = rgb.get_pixel(x,y);
                 let r = rgb_pixel[0];
                 let g = rgb_pixel[1];
                 let b = rgb_pixel[2];
 
-                /* Convert to appropiate color and style */
-                style = self.get_style(r,g,b);
-
                 /* Get window character */
-                let ch = window_analysis_ssim(&luma, x, y, &font_set)
-                            .to_string();
+                let ch = window_analysis_ssim(&luma, x, y, font_set);
 
-                /* Add ansi */
-                ansi.data.push(style.paint(ch));
+                /* Convert to appropiate color and style */
+                canvas.set(x, y, self.get_cell(ch, r,g,b));
             }
-            ansi.data.push(style_normal.paint("\n"));
         }
        
-        ansi
+        canvas
     }
 
     /// Convert RGB image to a text representation using ansi (24-bit) true color or 256 terminal colors,
     /// mapping the luma values of the image to the characters
     /// in a given character set.
-    fn ascii_gradient<'b>(&self, rgb: RgbImage, luma: GrayImage, char_set: &Vec<char>) -> AnsiImageResult<'b> {
-
-        /* Create Result */
-        let mut ansi = AnsiImageResult{ data: vec![] };
-
-        /* Create initial style for later modification */
-        let mut style = self.get_style(0,0,0);
-        let style_normal = ansi_term::Style::new();
-
+    fn ascii_gradient(&self, rgb: RgbImage, luma: GrayImage, char_set: &Vec<char>) -> AnsiImageResult {
         /* Get image dimensions */
         let width = rgb.width();
         let height = rgb.height();
 
+        /* Create Result */
+        let mut canvas = Canvas::new(width, height);
+
+        /* Gradient style does not depend on the pixel color */
+        let (fg, bg) = self.get_color(0,0,0);
+
         for y in (0..height) {
             for x in (0..width) {
                 /* Get window character */
-                let ch = luma_mapping(&luma, x, y, &char_set)
-                            .to_string();
+                let ch = luma_mapping(&luma, x, y, char_set);
 
-                /* Add ansi */
-                ansi.data.push(style.paint(ch));
+                canvas.set(x, y, self.styled_cell(ch, fg, bg));
             }
-            ansi.data.push(style_normal.paint("\n"));
         }
        
-        ansi
+        canvas
     }
 
 }
 
 
+impl Renderer for AnsiAscii {
+    /// Convert a decoded image to ascii representation with the current character set
+    fn render(&self, image: &DynamicImage) -> Result<AnsiImageResult, AnsiImageError> {
+        /* Get requested size of image (without scaling!!) for later */
+        let size = self.size_aspect_ratio(image.dimensions());
+
+        /* Resize image to satisfy all internal parameters */
+        let image = image.adjust_contrast(self.contrast)
+                        .brighten(self.brighten);
+        let mut image = self.image_resize_with_scale(&image);
+
+        /* Invert colors */
+        if self.invert {
+            image.invert();
+        }
+
+        /* Cast to luma with scaled size */
+        let luma = image.to_luma8();
+        /* Cast image to rgb but resizing to keep proportion rgb:luma => (1:1) : (scale.0 : scale.1) 
+         * by utilizing previously compute non scaled size
+         * */ 
+        let mut rgb = image.resize_exact(size.0, size.1, self.filter)
+                        .to_rgb8();
+
+        assert_eq!(rgb.width() * self.scale.0, luma.width());
+        assert_eq!(rgb.height() * self.scale.1, luma.height());
+
+        /* Build the adaptive palette of the image */
+        let terminal = matches!(self.color, AsciiColor::Terminalcolor);
+        let ascii = self.with_adaptive_palette(&rgb, terminal);
+
+        /* Dither the colors approximated on the palette */
+        if !matches!(self.color, AsciiColor::Fixed) {
+            ascii.dither_colors(&mut rgb, terminal);
+        }
+
+        let res =
+        match self.mode {
+            AsciiMode::Gradient => {
+                let char_set = self.char_set.chars()
+                                    .collect::<Vec<char>>();
+
+                ascii.ascii_gradient(rgb, luma, &char_set)
+            },
+            AsciiMode::PatternQuadrance => {
+                /* Create font set */
+                let mut ascii_font_set = self.char_set.chars()
+                                      .map(|c| AsciiFont::from(c))
+                                      .collect::<Vec<AsciiFont>>();
+                /* Dedup font set to increase convertion speed */
+                ascii_font_set.sort_unstable();
+                ascii_font_set.dedup();
+
+                ascii.ascii_pattern_quadrance(rgb, luma, &ascii_font_set)
+            },
+            AsciiMode::PatternSsim => {
+                /* Create font set */
+                let mut ascii_font_set = self.char_set.chars()
+                                      .map(|c| AsciiFont::from(c))
+                                      .collect::<Vec<AsciiFont>>();
+                /* Dedup font set to increase convertion speed */
+                ascii_font_set.sort_unstable();
+                ascii_font_set.dedup();
+
+                ascii.ascii_pattern_ssim(rgb, luma, &ascii_font_set)
+            },
+        };
+
+        Ok(res)
+    }
+}
+
+
 /// Analyze image with windows and calculate best fitting character (quadrance metric)
 ///
 /// Perform a windowing analysis of the image with 5x7 windows, and 
@@ -568,4 +566,24 @@ mod tests {
 
         result.save("../ascii_pattern_ssim_terminalcolor.txt");
     }
+
+    #[test]
+    fn test_convert_bytes_and_reader() {
+        let (w,h) = setup_image_size();
+        let image_path = setup_path();
+
+        let ascii = AnsiAscii::new()
+                            .true_color()
+                            .pattern_quadrance()
+                            .size(w, h);
+
+        let bytes = std::fs::read(&image_path).unwrap();
+
+        let from_path = ascii.convert(&image_path, "012345789").unwrap();
+        let from_bytes = ascii.convert_bytes(&bytes, "012345789").unwrap();
+        let from_reader = ascii.convert_reader(std::io::Cursor::new(&bytes), "012345789").unwrap();
+
+        assert_eq!(from_path, from_bytes);
+        assert_eq!(from_path, from_reader);
+    }
 }
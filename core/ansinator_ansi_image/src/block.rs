@@ -1,11 +1,15 @@
 //! Representation of an image in block.
 #![allow(dead_code, unused)]
 
-use crate::ansi::{AnsiImage, AnsiImageResult, Ansinator};
+use crate::ansi::{self, AnsiImage, AnsiImageResult, Ansinator, Renderer};
+use crate::canvas::{Canvas, Cell, CellColor};
 use crate::error::AnsiImageError;
-use image::{DynamicImage, GenericImageView, RgbImage};
+use crate::uniblock::{self, FitGlyphs};
+use ansinator_image_binarize::{Dithering, Threshold};
+use ansinator_terminal_colors::Quantizer;
+use image::{DynamicImage, GenericImageView, GrayImage, RgbImage};
 use std::default::Default;
-use ansi_term::Color;
+use std::io::{Read, Seek};
 
 #[derive(Debug, Clone, Copy)]
 pub enum BlockColor{
@@ -23,6 +27,14 @@ impl Default for BlockColor {
 pub enum BlockMode{
     Whole,
     Half,
+    /// Quadrants with the two colors fitted to each cell
+    Quadrant,
+    /// Quadrants of the binarized luma, without coloring
+    QuadrantMonochrome,
+    /// Octants with the two colors fitted to each cell
+    Octant,
+    /// Octants of the binarized luma, without coloring
+    OctantMonochrome,
 }
 
 impl Default for BlockMode {
@@ -35,160 +47,263 @@ pub type AnsiBlock = AnsiImage<BlockMode, BlockColor>;
 
 impl AnsiBlock {
     pub fn true_color(&self) -> Self {
-        Self { color: BlockColor::Truecolor, .. *self}
+        Self { color: BlockColor::Truecolor, .. self.clone()}
     }
     pub fn terminal_color(&self) -> Self {
-        Self { color: BlockColor::Terminalcolor, .. *self}
+        Self { color: BlockColor::Terminalcolor, .. self.clone()}
     }
     pub fn half(&self) -> Self {
-        Self { mode: BlockMode::Half, scale: (1,2), .. *self}
+        Self { mode: BlockMode::Half, scale: (1,2), .. self.clone()}
     }
     pub fn whole(&self) -> Self {
-        Self { mode: BlockMode::Whole, scale: (1,1), .. *self}
+        Self { mode: BlockMode::Whole, scale: (1,1), .. self.clone()}
     } 
+    /// Set quadrant mode, fitting the glyph and the two colors of each cell to its pixels
+    pub fn quadrant(&self) -> Self {
+        Self { mode: BlockMode::Quadrant, scale: FitGlyphs::Quadrant.scale(), .. self.clone()}
+    }
+    /// Set monochrome quadrant mode, binarizing the luma by Otsu's method or the manual threshold if set
+    pub fn quadrant_monochrome(&self) -> Self {
+        Self { mode: BlockMode::QuadrantMonochrome, scale: FitGlyphs::Quadrant.scale(), .. self.clone()}
+    }
+    /// Set octant mode, fitting the glyph and the two colors of each cell to its pixels
+    ///
+    /// Octants need a font supporting Unicode 16, [`Self::quadrant`] draws the same cells otherwise.
+    pub fn octant(&self) -> Self {
+        Self { mode: BlockMode::Octant, scale: FitGlyphs::Octant.scale(), .. self.clone()}
+    }
+    /// Set monochrome octant mode, binarizing the luma by Otsu's method or the manual threshold if set
+    pub fn octant_monochrome(&self) -> Self {
+        Self { mode: BlockMode::OctantMonochrome, scale: FitGlyphs::Octant.scale(), .. self.clone()}
+    }
+    /// Set manual threshold of the monochrome quadrant and octant modes
+    pub fn threshold(&self, value: u8) -> Self {
+        Self { has_threshold: true, threshold: value, .. self.clone()}
+    }
+    /// Color with an adaptive palette built for each image
+    pub fn quantize(&self, quantizer: Quantizer) -> Self {
+        Self { quantizer: Some(quantizer), .. self.clone()}
+    }
+    /// Dither the colors approximated on the terminal, color depth or adaptive palette
+    pub fn dither(&self, dithering: Dithering) -> Self {
+        Self { color_dithering: Some(dithering), .. self.clone()}
+    }
 
-    pub fn get_color(&self, r: u8, g:u8, b:u8, br:u8, bg:u8, bb: u8) -> ansi_term::Style {
+    pub fn get_color(&self, r: u8, g:u8, b:u8, br:u8, bg:u8, bb: u8) -> (Option<CellColor>, Option<CellColor>) {
         match self.color {
+        _ if self.adaptive_palette.is_some() => {
+           (self.adaptive_color(r,g,b), self.adaptive_color(br,bg,bb))
+        },
         BlockColor::Truecolor => {
-           Color::RGB(r,g,b).on(Color::RGB(br,bg,bb))
+           (Some(CellColor::Rgb(r,g,b)), Some(CellColor::Rgb(br,bg,bb)))
         },
         BlockColor::Terminalcolor => {
-            let frgd_index = ansinator_terminal_colors::TermColor::from(r, g, b)
-                            .index;
-            let bkgd_index = ansinator_terminal_colors::TermColor::from(br, bg, bb)
-                            .index;
-           Color::Fixed(frgd_index).on(Color::Fixed(bkgd_index))
+            let frgd_index = self.term_color(r, g, b);
+            let bkgd_index = self.term_color(br, bg, bb);
+           (Some(CellColor::Fixed(frgd_index)), Some(CellColor::Fixed(bkgd_index)))
         },
         }
     }
-    pub fn get_style(&self, r:u8, g:u8, b:u8, br: u8, bg: u8, bb:u8) -> ansi_term::Style {
-        let mut style =  self.get_color(r,g,b,br,bg,bb);
-        if self.bold {
-            style = style.bold()
-        }
-        if self.blink {
-            style = style.blink()
-        }
-        if self.underline {
-            style = style.underline()
-        }
-
-        style
+    pub fn get_cell(&self, ch: char, foreground: (u8,u8,u8), background: (u8,u8,u8)) -> Cell {
+        let (r, g, b) = foreground;
+        let (br, bg, bb) = background;
+        let (fg, bg) = self.get_color(r,g,b,br,bg,bb);
+        self.styled_cell(ch, fg, bg)
     }
 
+    /// Convert image file to block representation
     pub fn convert(&self, image_path: &str) -> Result<AnsiImageResult, AnsiImageError> {
-        /* Try opening the image */
-        let image = match image::open(image_path) {
-            Ok(image) => image,
-            Err(e) => return Err(AnsiImageError::ImageError(e)),
-        };
-    
-        /* Resize image to satisfy all internal parameters */
-        let image = image.adjust_contrast(self.contrast)
-                        .brighten(self.brighten);
-        let mut image = self.image_resize_with_scale(&image);
-        /* Invert colors */
-        if self.invert {
-            image.invert();
-        }
+        let image = ansi::open_image(image_path)?;
+        self.convert_image(&image)
+    }
 
-        //let size = self.size_aspect_ratio(image.dimensions());
-        /* Cast image to rgb */
-        //let rgb = image.resize_exact(size.0, size.1, self.filter)
-        let rgb = image.to_rgb8();
+    /// Convert encoded image bytes to block representation
+    pub fn convert_bytes(&self, bytes: &[u8]) -> Result<AnsiImageResult, AnsiImageError> {
+        let image = ansi::load_image_from_memory(bytes)?;
+        self.convert_image(&image)
+    }
 
-        let res =
-        match self.mode {
-            BlockMode::Half => {
-                self.convertion_half(rgb)
-            },
-            BlockMode::Whole => {
-                self.convertion_whole(rgb)
-            },
-        };
-        Ok(res)
+    /// Convert an encoded image read from `reader` to block representation
+    pub fn convert_reader<R: Read + Seek>(&self, reader: R) -> Result<AnsiImageResult, AnsiImageError> {
+        let image = ansi::load_image_from_reader(reader)?;
+        self.convert_image(&image)
+    }
+
+    /// Convert a decoded image to block representation
+    pub fn convert_image(&self, image: &DynamicImage) -> Result<AnsiImageResult, AnsiImageError> {
+        self.render(image)
     }
 
     /// Convert RGB image to a text representation using ansi (24-bit) true color or 256 terminal colors,
     /// with a proportion of 1:1 image pixel : ansi character
-    fn convertion_whole<'b>(&self, rgb: RgbImage) -> AnsiImageResult<'b> {
-        /* Create Result */
-        let mut ansi = AnsiImageResult{ data: vec![] };
-
-        /* Convert to appropiate color and style */
-        let mut style = self.get_style(0,0,0,0,0,0);
-        let style_normal = ansi_term::Style::new();
-
+    fn convertion_whole(&self, rgb: RgbImage) -> AnsiImageResult {
         /* Get image dimensions */
         let width = rgb.width();
         let height = rgb.height();
 
-        for y in (0..height).step_by(self.scale.1 as usize) {
-            for x in (0..width).step_by(self.scale.0 as usize) {
+        /* Create Result */
+        let (scale_w, scale_h) = self.scale;
+        let mut canvas = Canvas::new(width / scale_w, height / scale_h);
+
+        for y in (0..height).step_by(scale_h as usize) {
+            for x in (0..width).step_by(scale_w as usize) {
                 /* Get RGB Color */
-                let rgb_pixel = rgb.get_pixel(x+0,y+0);
+                let rgb_pixel = rgb.get_pixel(x,y);
                 let r = rgb_pixel[0];
                 let g = rgb_pixel[1];
                 let b = rgb_pixel[2];
 
                 /* Convert to appropiate color and style */
-                style = self.get_style(0,0,0,r,g,b);
-
-                let ch = " ".to_string();
-
-                /* Add ansi */
-                ansi.data.push(style.paint(ch));
+                canvas.set(x / scale_w, y / scale_h, self.get_cell(' ', (0,0,0), (r,g,b)));
             }
-            ansi.data.push(style_normal.paint("\n"));
         }
        
-        ansi
+        canvas
     }
 
     /// Convert RGB image to a text representation using ansi (24-bit) true color or 256 terminal colors,
     /// with a proportion of 1:2 image width : image height for each ansi char
-    fn convertion_half<'b>(&self, rgb: RgbImage) -> AnsiImageResult<'b> {
-        let upper_block = "\u{2580}";
-        /* Create Result */
-        let mut ansi = AnsiImageResult{ data: vec![] };
-
-        /* Create initial style for later modification */
-        let mut style = self.get_style(0,0,0,0,0,0);
-        let style_normal = ansi_term::Style::new();
+    fn convertion_half(&self, rgb: RgbImage) -> AnsiImageResult {
+        let upper_block = '\u{2580}';
 
         /* Get image dimensions */
         let width = rgb.width();
         let height = rgb.height();
 
-        for y in (0..height).step_by(self.scale.1 as usize) {
-            for x in (0..width).step_by(self.scale.0 as usize) {
+        /* Create Result */
+        let (scale_w, scale_h) = self.scale;
+        let mut canvas = Canvas::new(width / scale_w, height / scale_h);
+
+        for y in (0..height).step_by(scale_h as usize) {
+            for x in (0..width).step_by(scale_w as usize) {
                 /* Get RGB Color */
-                let rgb_pixel = rgb.get_pixel(x+0,y+0);
+                let rgb_pixel = rgb.get_pixel(x,y);
                 let r = rgb_pixel[0];
                 let g = rgb_pixel[1];
                 let b = rgb_pixel[2];
 
-                let lower_rgb_pixel = rgb.get_pixel(x+0,y+1);
+                let lower_rgb_pixel = rgb.get_pixel(x,y+1);
                 let br = lower_rgb_pixel[0];
                 let bg = lower_rgb_pixel[1];
                 let bb = lower_rgb_pixel[2];
 
                 /* Convert to appropiate color and style */
-                style = self.get_style(r,g,b,br,bg,bb);
+                canvas.set(x / scale_w, y / scale_h, self.get_cell(upper_block, (r,g,b), (br,bg,bb)));
+            }
+        }
+       
+        canvas
+    }
 
-                let ch = upper_block.to_string();
+    /// Convert RGB image to a text representation using ansi (24-bit) true color or 256 terminal colors,
+    /// with quadrant or octant characters whose glyph and colors are fitted to the pixels of each cell
+    fn convertion_fitted(&self, rgb: RgbImage, glyphs: FitGlyphs) -> AnsiImageResult {
+        /* Get image dimensions */
+        let width = rgb.width();
+        let height = rgb.height();
+
+        /* Create Result */
+        let (scale_w, scale_h) = self.scale;
+        let mut canvas = Canvas::new(width / scale_w, height / scale_h);
+
+        for y in (0..height).step_by(scale_h as usize) {
+            for x in (0..width).step_by(scale_w as usize) {
+                /* Get best partition and its colors */
+                let (mask, foreground, background) = uniblock::fit_window(&rgb, x, y, self.scale);
 
-                /* Add ansi */
-                ansi.data.push(style.paint(ch));
+                /* Convert to appropiate color and style */
+                let ch = glyphs.glyph(mask);
+                canvas.set(x / scale_w, y / scale_h, self.get_cell(ch, foreground, background));
             }
-            ansi.data.push(style_normal.paint("\n"));
         }
-       
-        ansi
+
+        canvas
+    }
+
+    /// Convert binarized Gray image to a text representation with quadrant or octant characters,
+    /// colored only by the fixed colors
+    fn convertion_monochrome(&self, luma: GrayImage, glyphs: FitGlyphs) -> AnsiImageResult {
+        /* Get image dimensions */
+        let width = luma.width();
+        let height = luma.height();
+
+        /* Create Result */
+        let (scale_w, scale_h) = self.scale;
+        let mut canvas = Canvas::new(width / scale_w, height / scale_h);
+        let (fg, bg) = self.fixed_color();
+
+        for y in (0..height).step_by(scale_h as usize) {
+            for x in (0..width).step_by(scale_w as usize) {
+                /* Get window character, set pixels in row-major order */
+                let mask = (0..scale_w * scale_h)
+                    .fold(0, |mask, i| mask | (luma.get_pixel(x + i % scale_w, y + i / scale_w)[0] / 255) << i);
+                let ch = glyphs.glyph(mask);
+
+                canvas.set(x / scale_w, y / scale_h, self.styled_cell(ch, fg, bg));
+            }
+        }
+
+        canvas
     }
 
 }
 
+impl Renderer for AnsiBlock {
+    /// Convert a decoded image to block representation
+    fn render(&self, image: &DynamicImage) -> Result<AnsiImageResult, AnsiImageError> {
+        /* Resize image to satisfy all internal parameters */
+        let image = image.adjust_contrast(self.contrast)
+                        .brighten(self.brighten);
+        let mut image = self.image_resize_with_scale(&image);
+        /* Invert colors */
+        if self.invert {
+            image.invert();
+        }
+
+        /* Monochrome modes only binarize the luma, without any palette */
+        if let BlockMode::QuadrantMonochrome | BlockMode::OctantMonochrome = self.mode {
+            let mut luma = image.to_luma8();
+            if self.has_threshold {
+                luma.threshold(self.threshold);
+            } else {
+                luma.otsu_threshold();
+            }
+            let glyphs = if let BlockMode::OctantMonochrome = self.mode { FitGlyphs::Octant } else { FitGlyphs::Quadrant };
+            return Ok(self.convertion_monochrome(luma, glyphs));
+        }
+
+        //let size = self.size_aspect_ratio(image.dimensions());
+        /* Cast image to rgb */
+        //let rgb = image.resize_exact(size.0, size.1, self.filter)
+        let mut rgb = image.to_rgb8();
+
+        /* Build the adaptive palette of the image */
+        let terminal = matches!(self.color, BlockColor::Terminalcolor);
+        let block = self.with_adaptive_palette(&rgb, terminal);
+
+        /* Dither the colors approximated on the palette */
+        block.dither_colors(&mut rgb, terminal);
+
+        let res =
+        match self.mode {
+            BlockMode::Half => {
+                block.convertion_half(rgb)
+            },
+            BlockMode::Whole => {
+                block.convertion_whole(rgb)
+            },
+            BlockMode::Quadrant => {
+                block.convertion_fitted(rgb, FitGlyphs::Quadrant)
+            },
+            BlockMode::Octant => {
+                block.convertion_fitted(rgb, FitGlyphs::Octant)
+            },
+            BlockMode::QuadrantMonochrome | BlockMode::OctantMonochrome => unreachable!(),
+        };
+        Ok(res)
+    }
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -293,4 +408,203 @@ mod tests {
         result.save("../block_half_terminalcolor.txt");
     }
 
+    #[test]
+    fn test_convert_bytes_and_reader() {
+        let (w,h) = setup_image_size();
+        let image_path = setup_path();
+
+        let block = AnsiBlock::new()
+                            .half()
+                            .size(w, h);
+
+        let bytes = std::fs::read(&image_path).unwrap();
+
+        let from_path = block.convert(&image_path).unwrap();
+        let from_bytes = block.convert_bytes(&bytes).unwrap();
+        let from_reader = block.convert_reader(std::io::Cursor::new(&bytes)).unwrap();
+
+        assert_eq!(from_path, from_bytes);
+        assert_eq!(from_path, from_reader);
+    }
+
+    #[test]
+    fn test_quantize() {
+        use ansinator_terminal_colors::QuantizeMethod;
+        use std::collections::HashSet;
+
+        let (w,h) = setup_image_size();
+        let image_path = setup_path();
+
+        let colors = |result: &AnsiImageResult| {
+            result.cells.iter()
+                .flat_map(|cell| [cell.fg, cell.bg])
+                .collect::<HashSet<Option<CellColor>>>()
+        };
+
+        let block = AnsiBlock::new()
+                            .half()
+                            .size(w, h)
+                            .quantize(Quantizer::new(8));
+
+        let truecolor = block.convert(&image_path).unwrap();
+        let truecolor_colors = colors(&truecolor);
+        assert!(truecolor_colors.len() <= 8);
+        assert!(truecolor_colors.iter().all(|color| matches!(color, Some(CellColor::Rgb(..)))));
+
+        let kmeans = block.quantize(Quantizer::new(8).method(QuantizeMethod::KMeans))
+                            .convert(&image_path).unwrap();
+        assert!(colors(&kmeans).len() <= 8);
+
+        /* Terminal colors are restricted to the palette of the color depth */
+        let terminal = block.terminal_color()
+                            .color_depth(ansinator_terminal_colors::ColorDepth::Ansi16)
+                            .convert(&image_path).unwrap();
+        let terminal_colors = colors(&terminal);
+        assert!(terminal_colors.len() <= 8);
+        assert!(terminal_colors.iter().all(|color| matches!(color, Some(CellColor::Fixed(index)) if *index < 16)));
+    }
+
+    #[test]
+    fn test_dither() {
+        use ansinator_terminal_colors::{ColorDepth, Theme};
+        use image::{GrayImage, Luma};
+
+        /* Horizontal gray gradient, one pixel for each half block */
+        let gradient = DynamicImage::ImageLuma8(GrayImage::from_fn(64, 16, |x, _| Luma([(x * 4) as u8])));
+        let theme = Theme::new();
+
+        /* Error between the mean gray of each 8 columns of the source and of the result */
+        let banding = |block: &AnsiBlock| -> f64 {
+            let result = block.convert_image(&gradient).unwrap();
+            let gray = |color: Option<CellColor>| match color {
+                Some(CellColor::Fixed(index)) => theme.color(index).0 as f64,
+                Some(CellColor::Rgb(r, _, _)) => r as f64,
+                None => 0.0,
+            };
+
+            (0..8).map(|band| {
+                let cells = result.cells.iter().enumerate()
+                    .filter(|(i, _)| (*i as u32 % 64) / 8 == band)
+                    .map(|(_, cell)| cell);
+                let mean = cells.map(|cell| gray(cell.fg) + gray(cell.bg)).sum::<f64>() / (2.0 * 8.0 * 8.0);
+                let expected = (0..8).map(|x| ((band * 8 + x) * 4) as f64).sum::<f64>() / 8.0;
+                (mean - expected).abs()
+            })
+            .sum()
+        };
+
+        let block = AnsiBlock::new()
+                            .half()
+                            .size(64, 8)
+                            .terminal_color()
+                            .color_depth(ColorDepth::Ansi16);
+
+        let undithered = banding(&block);
+        for dithering in [Dithering::FloydSteinberg, Dithering::Atkinson, Dithering::JarvisJudiceNinke,
+                          Dithering::Sierra, Dithering::Bayer2, Dithering::Bayer4, Dithering::Bayer8] {
+            let dithered = banding(&block.dither(dithering));
+            assert!(dithered * 2.0 < undithered, "{:?} {} {}", dithering, dithered, undithered);
+        }
+
+        /* Colors kept on true color are not dithered */
+        let truecolor = AnsiBlock::new().half().size(64, 8);
+        assert_eq!(truecolor.convert_image(&gradient).unwrap(),
+                   truecolor.dither(Dithering::FloydSteinberg).convert_image(&gradient).unwrap());
+    }
+
+    #[test]
+    fn test_quadrant() {
+        use ansinator_terminal_colors::ColorDepth;
+        use image::Rgb;
+
+        let (w,h) = setup_image_size();
+        let image_path = setup_path();
+
+        let block = AnsiBlock::new()
+                            .true_color()
+                            .quadrant()
+                            .size(w, h);
+
+        let result = block.convert(&image_path)
+                            .unwrap();
+
+        assert_eq!((result.width, result.height), (w, h));
+        result.save("../block_quadrant_truecolor.txt");
+
+        /* Diagonal of yellow pixels on dark blue */
+        let rgb = RgbImage::from_fn(8, 4, |x, y| if x % 2 == y % 2 { Rgb([255,255,0]) } else { Rgb([0,0,128]) });
+        let image = DynamicImage::ImageRgb8(rgb);
+
+        let result = AnsiBlock::new().true_color().quadrant().size(4, 2).convert_image(&image).unwrap();
+        assert!(result.cells.iter().all(|cell| {
+            cell.ch == '\u{259A}' && cell.fg == Some(CellColor::Rgb(255,255,0)) && cell.bg == Some(CellColor::Rgb(0,0,128))
+        }));
+
+        /* Monochrome, the yellow pixels are set */
+        let result = AnsiBlock::new().quadrant_monochrome().threshold(100).size(4, 2).convert_image(&image).unwrap();
+        assert!(result.cells.iter().all(|cell| cell.ch == '\u{259A}' && cell.fg.is_none() && cell.bg.is_none()));
+
+        let result = AnsiBlock::new().quadrant_monochrome().threshold(255).size(4, 2).convert_image(&image).unwrap();
+        assert!(result.cells.iter().all(|cell| cell.ch == ' '));
+
+        /* Monochrome cells keep the fixed colors, without palette nor dithering */
+        let monochrome = AnsiBlock::new().quadrant_monochrome().threshold(100).size(4, 2).set_foreground((255,0,0));
+        let result = monochrome.convert_image(&image).unwrap();
+        assert!(result.cells.iter().all(|cell| cell.fg == Some(CellColor::Rgb(255,0,0)) && cell.bg.is_none()));
+        assert_eq!(result, monochrome.quantize(Quantizer::new(2)).dither(Dithering::FloydSteinberg)
+                                     .convert_image(&image).unwrap());
+
+        /* Without colors, the brighter left half is set */
+        let rgb = RgbImage::from_fn(8, 4, |x, _| if x % 2 == 0 { Rgb([230,220,200]) } else { Rgb([20,30,40]) });
+        let image = DynamicImage::ImageRgb8(rgb);
+        let result = AnsiBlock::new().quadrant().color_depth(ColorDepth::None).size(4, 2).convert_image(&image).unwrap();
+        assert!(result.cells.iter().all(|cell| cell.ch == '\u{258C}' && cell.fg.is_none() && cell.bg.is_none()));
+    }
+
+    #[test]
+    fn test_octant() {
+        use crate::octant;
+        use ansinator_terminal_colors::ColorDepth;
+        use image::{imageops::FilterType, Rgb};
+
+        let (w,h) = setup_image_size();
+        let image_path = setup_path();
+
+        let block = AnsiBlock::new()
+                            .true_color()
+                            .octant()
+                            .size(w, h);
+
+        let result = block.convert(&image_path)
+                            .unwrap();
+
+        assert_eq!((result.width, result.height), (w, h));
+        result.save("../block_octant_truecolor.txt");
+
+        /* Upper row and lower three rows of different colors */
+        let rgb = RgbImage::from_fn(4, 8, |_, y| if y % 4 == 0 { Rgb([255,255,0]) } else { Rgb([0,0,128]) });
+        let image = DynamicImage::ImageRgb8(rgb);
+        let result = AnsiBlock::new().true_color().octant().size(2, 2).convert_image(&image).unwrap();
+        assert!(result.cells.iter().all(|cell| {
+            cell.ch == '\u{1FB82}' && cell.fg == Some(CellColor::Rgb(255,255,0)) && cell.bg == Some(CellColor::Rgb(0,0,128))
+        }));
+
+        /* Without colors, the brighter upper row is set */
+        let result = AnsiBlock::new().octant().color_depth(ColorDepth::None).size(2, 2).convert_image(&image).unwrap();
+        assert!(result.cells.iter().all(|cell| cell.ch == '\u{1FB82}' && cell.fg.is_none() && cell.bg.is_none()));
+        let result = AnsiBlock::new().octant().plain().invert().size(2, 2).convert_image(&image).unwrap();
+        assert!(result.cells.iter().all(|cell| cell.ch == '\u{2586}'));
+
+        /* Monochrome octants match the window analysis of the binarized luma */
+        let image = ansi::open_image(&image_path).unwrap();
+        let block = AnsiBlock::new().octant_monochrome().threshold(100).size(30, 10);
+        let result = block.convert_image(&image).unwrap();
+
+        let mut luma = block.image_resize_with_scale(&image).to_luma8();
+        luma.threshold(100);
+        for (i, cell) in result.cells.iter().enumerate() {
+            let (x, y) = (i as u32 % 30 * 2, i as u32 / 30 * 4);
+            assert_eq!(cell.ch, octant::window_analysis(&luma, x, y));
+        }
+    }
 }
@@ -1,17 +1,20 @@
 //! Representation of an image in braile 8-dot.
 #![allow(dead_code, unused)]
 
-use crate::ansi::{AnsiImage, AnsiImageResult, Ansinator};
+use crate::ansi::{self, AnsiImage, AnsiImageResult, Ansinator, Renderer};
+use crate::canvas::{Canvas, Cell, CellColor};
 use crate::error::AnsiImageError;
-use ansinator_image_binarize::Threshold;
-use image::{DynamicImage, GrayImage};
+use ansinator_image_binarize::{AdaptiveThreshold, Dither, Dithering, Threshold, ThresholdMethod};
+use image::{DynamicImage, GrayImage, RgbImage};
 use std::default::Default;
-use ansi_term::Color;
+use std::io::{Read, Seek};
 
 
 #[derive(Debug, Clone, Copy)]
 pub enum BraileColor {
-    Fixed
+    Truecolor,
+    Terminalcolor,
+    Fixed,
 }
 
 impl Default for BraileColor {
@@ -24,6 +27,9 @@ impl Default for BraileColor {
 pub enum BraileMode {
     ManualThreshold,
     OtsuThreshold,
+    AutoThreshold(ThresholdMethod),
+    Dither(Dithering),
+    AdaptiveThreshold(AdaptiveThreshold),
 }
 
 impl Default for BraileMode {
@@ -36,66 +42,122 @@ pub type AnsiBraile = AnsiImage<BraileMode, BraileColor>;
 
 impl AnsiBraile {
     pub fn threshold(&self, value: u8) -> Self {
-        Self { mode: BraileMode::ManualThreshold, has_threshold: true, threshold: value, scale: (2,4), .. *self}
+        Self { mode: BraileMode::ManualThreshold, has_threshold: true, threshold: value, scale: (2,4), .. self.clone()}
     }
     pub fn otsu_threshold(&self) -> Self {
-        Self { mode: BraileMode::OtsuThreshold, scale: (2,4), .. *self}
+        Self { mode: BraileMode::OtsuThreshold, scale: (2,4), .. self.clone()}
     } 
+    /// Binarize with the threshold of an automatic global method
+    pub fn auto_threshold(&self, method: ThresholdMethod) -> Self {
+        Self { mode: BraileMode::AutoThreshold(method), scale: (2,4), .. self.clone()}
+    }
+    /// Binarize with error diffusion or ordered dithering
+    pub fn dither(&self, dithering: Dithering) -> Self {
+        Self { mode: BraileMode::Dither(dithering), scale: (2,4), .. self.clone()}
+    }
+    /// Binarize with the local threshold of the window around each pixel
+    pub fn adaptive_threshold(&self, adaptive: AdaptiveThreshold) -> Self {
+        Self { mode: BraileMode::AdaptiveThreshold(adaptive), scale: (2,4), .. self.clone()}
+    }
 
-    pub fn get_color(&self) -> ansi_term::Style {
-        let (r,g,b) = self.foreground;
-        let (br,bg,bb) = self.background;
+    /// Coloring with true color (RGB8)
+    pub fn true_color(&self) -> Self {
+        Self { color: BraileColor::Truecolor, .. self.clone()}
+    }
+    /// Coloring with terminal colors (256 terminal color)
+    pub fn terminal_color(&self) -> Self {
+        Self { color: BraileColor::Terminalcolor, .. self.clone()}
+    }
+    /// Color the background with the mean of the unset dots of each cell,
+    /// only with true color or terminal colors
+    pub fn mean_background(&self) -> Self {
+        Self { mean_background: true, .. self.clone()}
+    }
+
+    /// Get the colors of a cell from the mean of its set dots (foreground)
+    /// and of its unset dots (background) for the current coloring
+    pub fn get_color(&self, foreground: (u8,u8,u8), background: (u8,u8,u8)) -> (Option<CellColor>, Option<CellColor>) {
+        let (r, g, b) = foreground;
+        let (br, bg, bb) = background;
         match self.color {
+            BraileColor::Truecolor => {
+                (Some(CellColor::Rgb(r,g,b)), self.mean_background.then_some(CellColor::Rgb(br,bg,bb)))
+            },
+            BraileColor::Terminalcolor => {
+                let frgd_index = self.term_color(r, g, b);
+                let bkgd = self.mean_background.then(|| CellColor::Fixed(self.term_color(br, bg, bb)));
+                (Some(CellColor::Fixed(frgd_index)), bkgd)
+            },
             BraileColor::Fixed => {
-                match (self.has_foreground, self.has_background) {
-                    (false, false) => {
-                        ansi_term::Style::new()
-                    },
-                    (false, true) => {
-                        Color::RGB(0,0,0).on(Color::RGB(br,bg,bb))
-                    },
-                    (true, false) => {
-                        let (r, g, b) = self.foreground;
-                        Color::RGB(r,g,b).normal()
-                    },
-                    (true, true) => {
-                        let (r, g, b) = self.foreground;
-                        let (br, bg, bb) = self.background;
-                        Color::RGB(r,g,b).on(Color::RGB(br,bg,bb))
-                    },
-                }
+                self.fixed_color()
             },
         }
     }
-    pub fn get_style(&self) -> ansi_term::Style {
-        let mut style =  self.get_color();
-        if self.bold {
-            style = style.bold()
-        }
-        if self.blink {
-            style = style.blink()
-        }
-        if self.underline {
-            style = style.underline()
-        }
-
-        style
+    pub fn get_cell(&self, ch: char, foreground: (u8,u8,u8), background: (u8,u8,u8)) -> Cell {
+        let (fg, bg) = self.get_color(foreground, background);
+        self.styled_cell(ch, fg, bg)
     }
 
+    /// Convert image file to braile representation
     pub fn convert(&self, image_path: &str) -> Result<AnsiImageResult, AnsiImageError> {
-        
-        /* Try opening the image */
-        let image = match image::open(image_path) {
-            Ok(image) => image,
-            Err(e) => return Err(AnsiImageError::ImageError(e)),
-        };
+        let image = ansi::open_image(image_path)?;
+        self.convert_image(&image)
+    }
+
+    /// Convert encoded image bytes to braile representation
+    pub fn convert_bytes(&self, bytes: &[u8]) -> Result<AnsiImageResult, AnsiImageError> {
+        let image = ansi::load_image_from_memory(bytes)?;
+        self.convert_image(&image)
+    }
+
+    /// Convert an encoded image read from `reader` to braile representation
+    pub fn convert_reader<R: Read + Seek>(&self, reader: R) -> Result<AnsiImageResult, AnsiImageError> {
+        let image = ansi::load_image_from_reader(reader)?;
+        self.convert_image(&image)
+    }
+
+    /// Convert a decoded image to braile representation
+    pub fn convert_image(&self, image: &DynamicImage) -> Result<AnsiImageResult, AnsiImageError> {
+        self.render(image)
+    }
+
+    fn braile(&self, rgb: &RgbImage, luma: GrayImage) -> AnsiImageResult {
+
+        /* Get image dimensions */
+        let width = luma.width();
+        let height = luma.height();
+
+        /* Create Result */
+        let mut canvas = Canvas::new(width / 2, height / 4);
+
+        for y in (0..height).step_by(4) {
+            for x in (0..width).step_by(2) {
+
+                /* Get window character */
+                let ch = window_analysis(&luma, x,y);
+
+                /* Get mean colors of the set and unset dots */
+                let (foreground, background) = ansi::window_colors(rgb, &luma, x, y, self.scale);
 
+                /* Convert to appropiate color and style */
+                canvas.set(x / 2, y / 4, self.get_cell(ch, foreground, background));
+            }
+        }
+       
+        canvas
+    }
+}
+
+impl Renderer for AnsiBraile {
+    /// Convert a decoded image to braile representation
+    fn render(&self, image: &DynamicImage) -> Result<AnsiImageResult, AnsiImageError> {
         /* Resize image to satisfy all internal parameters */
         let image = image.adjust_contrast(self.contrast)
                         .brighten(self.brighten);
         let image = self.image_resize_with_scale(&image);
 
-        /* Cast image to luma */
+        /* Keep the colors of the pixels and cast image to luma */
+        let rgb = image.to_rgb8();
         let mut luma = image.to_luma8();
 
         /* Binarize */
@@ -105,6 +167,15 @@ impl AnsiBraile {
             },
             BraileMode::OtsuThreshold => {
                 luma.otsu_threshold();
+            },
+            BraileMode::AutoThreshold(method) => {
+                luma.auto_threshold(method);
+            },
+            BraileMode::Dither(dithering) => {
+                luma.dither(dithering);
+            },
+            BraileMode::AdaptiveThreshold(adaptive) => {
+                luma.adaptive_threshold(adaptive);
             }
         }
         /* Invert colors */
@@ -113,38 +184,9 @@ impl AnsiBraile {
         }
 
         /* Analyze windows and convert */
-        let res = self.braile(luma);
+        let res = self.braile(&rgb, luma);
         Ok(res)
     }
-
-    fn braile<'b>(&self, luma: GrayImage) -> AnsiImageResult<'b> {
-
-        /* Create Result */
-        let mut ansi = AnsiImageResult{ data: vec![] };
-
-        /* Convert to appropiate color and style */
-        let style = self.get_style();
-
-        /* Get image dimensions */
-        let width = luma.width();
-        let height = luma.height();
-
-        for y in (0..height).step_by(4) {
-            for x in (0..width).step_by(2) {
-
-                /* Get window character */
-                let ch = window_analysis(&luma, x,y)
-                            .to_string();
-
-                /* Add ansi */
-                ansi.data.push(style.paint(ch));
-            }
-            let style_normal = ansi_term::Style::new();
-            ansi.data.push(style_normal.paint("\n"));
-        }
-       
-        ansi
-    }
 }
 
 
@@ -295,4 +337,135 @@ mod tests {
 
         result.save("../braile_manual_fixcolor.txt");
     }
+
+    #[test]
+    fn test_convert_bytes_and_reader() {
+        let (w,h) = setup_image_size();
+        let image_path = setup_path();
+
+        let braile = AnsiBraile::new()
+                            .otsu_threshold()
+                            .size(w, h);
+
+        let bytes = std::fs::read(&image_path).unwrap();
+
+        let from_path = braile.convert(&image_path).unwrap();
+        let from_bytes = braile.convert_bytes(&bytes).unwrap();
+        let from_reader = braile.convert_reader(std::io::Cursor::new(&bytes)).unwrap();
+
+        assert_eq!(from_path, from_bytes);
+        assert_eq!(from_path, from_reader);
+    }
+
+    #[test]
+    fn test_dither() {
+        use image::{GrayImage, Luma};
+
+        let (w,h) = setup_image_size();
+        let image_path = setup_path();
+
+        let braile = AnsiBraile::new()
+                            .dither(Dithering::FloydSteinberg)
+                            .size(w, h);
+
+        let result = braile.convert(&image_path)
+                            .unwrap();
+
+        result.save("../braile_dither.txt");
+
+        /* A uniform midtone mixes set and unset dots instead of a single character */
+        let gray = DynamicImage::ImageLuma8(GrayImage::from_pixel(40, 24, Luma([128])));
+        for dithering in [Dithering::FloydSteinberg, Dithering::Atkinson, Dithering::JarvisJudiceNinke,
+                          Dithering::Sierra, Dithering::Bayer2, Dithering::Bayer4, Dithering::Bayer8] {
+            let result = AnsiBraile::new()
+                            .dither(dithering)
+                            .size(20, 6)
+                            .convert_image(&gray)
+                            .unwrap();
+
+            assert!(result.cells.iter().all(|cell| cell.ch != '\u{2800}' && cell.ch != '\u{28FF}'), "{:?}", dithering);
+        }
+    }
+
+    #[test]
+    fn test_adaptive_threshold() {
+        use ansinator_image_binarize::AdaptiveMethod;
+
+        let (w,h) = setup_image_size();
+        let image_path = setup_path();
+
+        for method in [AdaptiveMethod::MeanC, AdaptiveMethod::GaussianC, AdaptiveMethod::Niblack,
+                       AdaptiveMethod::Sauvola, AdaptiveMethod::Bradley] {
+            let braile = AnsiBraile::new()
+                                .adaptive_threshold(AdaptiveThreshold::new(method).window(21))
+                                .size(w, h);
+
+            let result = braile.convert(&image_path)
+                                .unwrap();
+
+            assert_eq!((result.width, result.height), (w, h));
+            if method == AdaptiveMethod::Sauvola {
+                result.save("../braile_sauvola.txt");
+            }
+        }
+    }
+
+    #[test]
+    fn test_auto_threshold() {
+        let (w,h) = setup_image_size();
+        let image_path = setup_path();
+
+        /* Otsu's method is the default automatic method */
+        let otsu = AnsiBraile::new().otsu_threshold().size(w, h).convert(&image_path).unwrap();
+        let auto = AnsiBraile::new().auto_threshold(ThresholdMethod::Otsu).size(w, h).convert(&image_path).unwrap();
+        assert_eq!(otsu, auto);
+
+        let braile = AnsiBraile::new()
+                            .auto_threshold(ThresholdMethod::Triangle)
+                            .size(w, h);
+
+        let result = braile.convert(&image_path)
+                            .unwrap();
+
+        result.save("../braile_triangle.txt");
+    }
+
+    #[test]
+    fn test_color() {
+        use image::Rgb;
+
+        let (w,h) = setup_image_size();
+        let image_path = setup_path();
+
+        let braile = AnsiBraile::new()
+                            .otsu_threshold()
+                            .true_color()
+                            .mean_background()
+                            .size(w, h);
+
+        let result = braile.convert(&image_path)
+                            .unwrap();
+
+        result.save("../braile_truecolor.txt");
+
+        /* Left column of dots yellow and right column dark blue */
+        let rgb = RgbImage::from_fn(8, 8, |x, _| if x % 2 == 0 { Rgb([255,255,0]) } else { Rgb([0,0,128]) });
+        let image = DynamicImage::ImageRgb8(rgb);
+        let braile = AnsiBraile::new().threshold(100).size(4, 2);
+
+        let result = braile.true_color().convert_image(&image).unwrap();
+        assert!(result.cells.iter().all(|cell| cell.ch == '\u{2847}'));
+        assert!(result.cells.iter().all(|cell| cell.fg == Some(CellColor::Rgb(255,255,0)) && cell.bg.is_none()));
+
+        let result = braile.true_color().mean_background().convert_image(&image).unwrap();
+        assert!(result.cells.iter().all(|cell| cell.bg == Some(CellColor::Rgb(0,0,128))));
+
+        let result = braile.terminal_color().mean_background().convert_image(&image).unwrap();
+        let (fg, bg) = (braile.term_color(255,255,0), braile.term_color(0,0,128));
+        assert!(result.cells.iter().all(|cell| cell.fg == Some(CellColor::Fixed(fg)) && cell.bg == Some(CellColor::Fixed(bg))));
+
+        /* Inverted, the dark blue dots are set */
+        let result = braile.invert().true_color().convert_image(&image).unwrap();
+        assert!(result.cells.iter().all(|cell| cell.ch == '\u{28B8}' && cell.fg == Some(CellColor::Rgb(0,0,128))));
+    }
 }
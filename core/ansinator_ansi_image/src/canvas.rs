@@ -0,0 +1,173 @@
+//! A structured representation of a convertion result.
+//!
+//! This module defines:
+//! + Canvas: A grid of cells, the result of every convertion mode.
+//! + Cell: A character along its colors and attributes.
+//! + CellColor: Color of a cell foreground or background.
+//! + CellAttributes: Bold, blink and underline attributes of a cell.
+
+use ansinator_terminal_colors::{Theme, TERM256_COLOR};
+
+/// Color of a cell foreground or background
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CellColor {
+    /// True color (24-bit)
+    Rgb(u8, u8, u8),
+    /// 256 terminal color index (8-bit)
+    Fixed(u8),
+}
+
+impl CellColor {
+    /// Get the RGB value of the color, terminal colors use their standard value
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        match *self {
+            CellColor::Rgb(r, g, b) => (r, g, b),
+            CellColor::Fixed(index) => TERM256_COLOR[index as usize],
+        }
+    }
+
+    /// Get the RGB value of the color, terminal colors use their value on `theme`
+    pub fn themed_rgb(&self, theme: &Theme) -> (u8, u8, u8) {
+        match *self {
+            CellColor::Rgb(r, g, b) => (r, g, b),
+            CellColor::Fixed(index) => theme.color(index),
+        }
+    }
+}
+
+/// Text attributes of a cell
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CellAttributes {
+    pub bold: bool,
+    pub blink: bool,
+    pub underline: bool,
+}
+
+/// A character along its colors and attributes
+///
+/// A color set to `None` means the terminal default color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub ch: char,
+    pub fg: Option<CellColor>,
+    pub bg: Option<CellColor>,
+    pub attrs: CellAttributes,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self { ch: ' ', fg: None, bg: None, attrs: CellAttributes::default() }
+    }
+}
+
+impl Cell {
+    /// Create a cell with the given character and default colors and attributes
+    pub fn new(ch: char) -> Self {
+        Self { ch, .. Self::default() }
+    }
+
+    /// Check if two cells share the same colors and attributes
+    pub fn same_style(&self, cell: &Cell) -> bool {
+        self.fg == cell.fg && self.bg == cell.bg && self.attrs == cell.attrs
+    }
+}
+
+/// Grid of cells with `width` columns and `height` rows
+///
+/// Cells are stored in row-major order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Canvas {
+    pub width: u32,
+    pub height: u32,
+    pub cells: Vec<Cell>,
+}
+
+impl Canvas {
+    /// Create a canvas of the given size filled with default cells
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![Cell::default(); width as usize * height as usize],
+        }
+    }
+
+    /// Gets a reference to the cell at location `(x, y)` or returns `None` if
+    /// the index is out of the bounds `(width, height)`.
+    pub fn get(&self, x: u32, y: u32) -> Option<&Cell> {
+        if x < self.width && y < self.height {
+            self.cells.get(self.index(x, y))
+        } else {
+            None
+        }
+    }
+
+    /// Gets a mutable reference to the cell at location `(x, y)` or returns `None` if
+    /// the index is out of the bounds `(width, height)`.
+    pub fn get_mut(&mut self, x: u32, y: u32) -> Option<&mut Cell> {
+        if x < self.width && y < self.height {
+            let index = self.index(x, y);
+            self.cells.get_mut(index)
+        } else {
+            None
+        }
+    }
+
+    /// Put a cell at location `(x, y)`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `(x, y)` is out of the bounds `(width, height)`.
+    pub fn set(&mut self, x: u32, y: u32, cell: Cell) {
+        assert!(x < self.width && y < self.height,
+                "Canvas index {:?} out of bounds {:?}",
+                (x, y),
+                (self.width, self.height)
+                );
+        let index = self.index(x, y);
+        self.cells[index] = cell;
+    }
+
+    /// Iterate over the rows of the canvas
+    pub fn rows(&self) -> impl Iterator<Item = &[Cell]> {
+        /* Avoid a zero chunk size on empty canvas */
+        self.cells.chunks(self.width.max(1) as usize)
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_indexing() {
+        let mut canvas = Canvas::new(3, 2);
+        let cell = Cell { ch: 'x', fg: Some(CellColor::Fixed(9)), .. Cell::default() };
+
+        canvas.set(2, 1, cell);
+
+        assert_eq!(canvas.cells.len(), 6);
+        assert_eq!(canvas.get(2, 1), Some(&cell));
+        assert_eq!(canvas.cells[5], cell);
+        assert_eq!(canvas.get(0, 0), Some(&Cell::default()));
+        assert_eq!(canvas.get(3, 0), None);
+        assert_eq!(canvas.get(0, 2), None);
+    }
+
+    #[test]
+    fn test_rows() {
+        let mut canvas = Canvas::new(2, 3);
+        canvas.get_mut(1, 2).unwrap().ch = '#';
+
+        let rows = canvas.rows().collect::<Vec<&[Cell]>>();
+
+        assert_eq!(rows.len(), 3);
+        assert!(rows.iter().all(|row| row.len() == 2));
+        assert_eq!(rows[2][1].ch, '#');
+    }
+}
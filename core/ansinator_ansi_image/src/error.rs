@@ -7,6 +7,7 @@ pub enum AnsiImageError {
     FileError(std::io::Error),
     WriteError(std::io::Error),
     ImageError(image::ImageError),
+    ReadError(std::io::Error),
 }
 
 impl fmt::Display for AnsiImageError {
@@ -15,6 +16,7 @@ impl fmt::Display for AnsiImageError {
             Self::FileError(e) =>  write!(f, "Error creating save file \"{}\"", e),
             Self::WriteError(e) =>  write!(f, "Error writing to save file \"{}\"", e),
             Self::ImageError(e) =>  write!(f, "Error opening image: \"{}\"", e),
+            Self::ReadError(e) =>  write!(f, "Error reading image: \"{}\"", e),
         }
     }
 }
@@ -0,0 +1,104 @@
+//! Geometry of unicode block characters.
+//!
+//! Block characters split a cell in a grid of sub-cells, either set
+//! (painted with the foreground) or unset (painted with the background),
+//! which allows drawing them exactly without depending on a font.
+
+use crate::octant;
+
+/// Grid of sub-cells of a block character
+///
+/// Each sub-cell is a bit of `mask` in row-major order,
+/// so the sub-cell at `(column, row)` is the bit `row * columns + column`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockGlyph {
+    pub columns: u32,
+    pub rows: u32,
+    pub mask: u8,
+}
+
+impl BlockGlyph {
+    fn new(columns: u32, rows: u32, mask: u8) -> Self {
+        Self { columns, rows, mask }
+    }
+
+    /// Check if the sub-cell at `(column, row)` is set
+    pub fn is_set(&self, column: u32, row: u32) -> bool {
+        self.mask >> (row * self.columns + column) & 1 == 1
+    }
+
+    /// Get the block geometry of a character, `None` if it is not a block character
+    ///
+    /// Supports full, half, quadrant, sextant and octant block characters.
+    pub fn from(ch: char) -> Option<Self> {
+        match ch {
+            /* Full block */
+            '\u{2588}' => Some(Self::new(1, 1, 0b1)),
+            /* Upper and lower half blocks */
+            '\u{2580}' => Some(Self::new(1, 2, 0b01)),
+            '\u{2584}' => Some(Self::new(1, 2, 0b10)),
+            /* Left and right half blocks */
+            '\u{258C}' => Some(Self::new(2, 1, 0b01)),
+            '\u{2590}' => Some(Self::new(2, 1, 0b10)),
+            /* Quadrants */
+            '\u{2596}'..='\u{259F}' => {
+                const QUADRANTS: [u8; 10] = [4, 8, 1, 13, 9, 7, 11, 2, 6, 14];
+                Some(Self::new(2, 2, QUADRANTS[ch as usize - 0x2596]))
+            },
+            /* Sextants, skipping the patterns of the left and right half blocks */
+            '\u{1FB00}'..='\u{1FB3B}' => {
+                let mut mask = (ch as u32 - 0x1FB00 + 1) as u8;
+                if mask >= 21 {
+                    mask += 1;
+                }
+                if mask >= 42 {
+                    mask += 1;
+                }
+                Some(Self::new(2, 3, mask))
+            },
+            /* Octants and the one quarter and three quarters blocks they reuse */
+            ' ' => None,
+            _ => octant::octant_mask(ch).map(|mask| Self::new(2, 4, mask)),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sextants() {
+        assert_eq!(BlockGlyph::from('\u{1FB00}').unwrap().mask, 1);
+        assert_eq!(BlockGlyph::from('\u{1FB13}').unwrap().mask, 20);
+        assert_eq!(BlockGlyph::from('\u{1FB14}').unwrap().mask, 22);
+        assert_eq!(BlockGlyph::from('\u{1FB27}').unwrap().mask, 41);
+        assert_eq!(BlockGlyph::from('\u{1FB28}').unwrap().mask, 43);
+        assert_eq!(BlockGlyph::from('\u{1FB3B}').unwrap().mask, 62);
+    }
+
+    #[test]
+    fn test_octants() {
+        assert_eq!(BlockGlyph::from('\u{1CD00}'), Some(BlockGlyph::new(2, 4, 0x04)));
+        assert_eq!(BlockGlyph::from('\u{1CDE5}'), Some(BlockGlyph::new(2, 4, 0xFE)));
+        assert_eq!(BlockGlyph::from('\u{1FB82}'), Some(BlockGlyph::new(2, 4, 0x03)));
+
+        let glyph = BlockGlyph::from('\u{2586}').unwrap();
+        assert!(!glyph.is_set(1, 0));
+        assert!(glyph.is_set(1, 1));
+        assert!(glyph.is_set(0, 3));
+    }
+
+    #[test]
+    fn test_sub_cells() {
+        let quadrant = BlockGlyph::from('\u{259E}').unwrap();
+
+        assert!(!quadrant.is_set(0, 0));
+        assert!(quadrant.is_set(1, 0));
+        assert!(quadrant.is_set(0, 1));
+        assert!(!quadrant.is_set(1, 1));
+        assert_eq!(BlockGlyph::from('a'), None);
+        assert_eq!(BlockGlyph::from(' '), None);
+    }
+}
@@ -0,0 +1,316 @@
+//! HTML serialization of a convertion result.
+//!
+//! The canvas is written inside a `<pre>` element, where each run of
+//! adjacent cells sharing the same colors and attributes is merged into
+//! a single `<span>`, styled either inline or by classes.
+
+use crate::ansi::AnsiImageResult;
+use crate::canvas::{Cell, CellColor};
+use crate::error::AnsiImageError;
+use ansinator_terminal_colors::Theme;
+use std::collections::BTreeSet;
+use std::io::Write;
+
+/// Class of the `<pre>` element holding the convertion
+const PRE_CLASS: &str = "ansinator";
+
+/// Style of the `<pre>` element, keeping block characters together
+const PRE_STYLE: &str = "font-family:monospace;line-height:1";
+
+/// Animation used for the blink attribute
+const BLINK_KEYFRAMES: &str = "@keyframes ansinator-blink { 50% { opacity: 0; } }";
+
+/// Options of the HTML serialization
+///
+/// By default a fragment with inline styles is produced, note that blinking
+/// needs the `ansinator-blink` animation defined by standalone pages and by
+/// the `<style>` element written along classes. Terminal colors take
+/// their xterm value unless a theme is given.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HtmlOptions {
+    standalone: bool,
+    classes: bool,
+    theme: Theme,
+}
+
+impl HtmlOptions {
+    /// Create the default options, an inline styled fragment
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Produce a standalone page instead of a fragment
+    pub fn standalone(&self) -> Self {
+        Self { standalone: true, .. self.clone() }
+    }
+
+    /// Produce a fragment to be embedded in another page
+    pub fn fragment(&self) -> Self {
+        Self { standalone: false, .. self.clone() }
+    }
+
+    /// Style spans by classes defined in a `<style>` element
+    pub fn classes(&self) -> Self {
+        Self { classes: true, .. self.clone() }
+    }
+
+    /// Style spans by inline `style` attributes
+    pub fn inline(&self) -> Self {
+        Self { classes: false, .. self.clone() }
+    }
+
+    /// Set the theme giving the RGB value of terminal colors
+    pub fn theme(&self, theme: &Theme) -> Self {
+        Self { theme: theme.clone(), .. self.clone() }
+    }
+}
+
+impl AnsiImageResult {
+    /// Get the HTML representation
+    pub fn to_html(&self, options: &HtmlOptions) -> String {
+        let mut buffer: Vec<u8> = vec![];
+        /* Writing to memory never fails */
+        self.write_html(&mut buffer, options).unwrap();
+
+        String::from_utf8(buffer).unwrap()
+    }
+
+    /// Write the HTML representation to `writer`
+    pub fn write_html<W: Write + ?Sized>(&self, writer: &mut W, options: &HtmlOptions) -> Result<(), AnsiImageError> {
+        let mut head = String::new();
+
+        if options.standalone {
+            head.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+            head.push_str("<title>ansinator</title>\n");
+        }
+        /* Inline styled fragments do not depend on a style element */
+        if options.standalone || options.classes {
+            head.push_str("<style>\n");
+            if options.standalone {
+                head.push_str("body { background-color: #000000; color: #ffffff; }\n");
+            }
+            head.push_str(BLINK_KEYFRAMES);
+            head.push('\n');
+            if options.classes {
+                head.push_str(&self.stylesheet(&options.theme));
+            }
+            head.push_str("</style>\n");
+        }
+        if options.standalone {
+            head.push_str("</head>\n<body>\n");
+        }
+        head.push_str(&format!("<pre class=\"{}\" style=\"{}\">", PRE_CLASS, PRE_STYLE));
+
+        if let Err(e) = writer.write_all(head.as_bytes()) {
+            return Err(AnsiImageError::WriteError(e));
+        }
+
+        for row in self.rows() {
+            let line = html_row(row, options);
+            if let Err(e) = writer.write_all(line.as_bytes()) {
+                return Err(AnsiImageError::WriteError(e));
+            }
+        }
+
+        let mut tail = String::from("</pre>\n");
+        if options.standalone {
+            tail.push_str("</body>\n</html>\n");
+        }
+
+        match writer.write_all(tail.as_bytes()) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(AnsiImageError::WriteError(e)),
+        }
+    }
+
+    /// Get the style rules of every class used by the cells
+    fn stylesheet(&self, theme: &Theme) -> String {
+        let mut fgs = BTreeSet::new();
+        let mut bgs = BTreeSet::new();
+        for cell in self.cells.iter() {
+            fgs.extend(cell.fg.map(|color| color.themed_rgb(theme)));
+            bgs.extend(cell.bg.map(|color| color.themed_rgb(theme)));
+        }
+
+        let mut rules = String::new();
+        for (r, g, b) in fgs {
+            rules.push_str(&format!(".f-{0} {{ color: #{0}; }}\n", hex(r, g, b)));
+        }
+        for (r, g, b) in bgs {
+            rules.push_str(&format!(".b-{0} {{ background-color: #{0}; }}\n", hex(r, g, b)));
+        }
+        rules.push_str(".bold { font-weight: bold; }\n");
+        rules.push_str(".underline { text-decoration: underline; }\n");
+        rules.push_str(".blink { animation: ansinator-blink 1s step-end infinite; }\n");
+
+        rules
+    }
+}
+
+/// Get a row of cells as HTML, merging adjacent cells of the same style into one span
+fn html_row(row: &[Cell], options: &HtmlOptions) -> String {
+    let mut line = String::new();
+    let mut start = 0;
+
+    while start < row.len() {
+        let style = row[start];
+        let end = row[start..].iter()
+            .position(|cell| !cell.same_style(&style))
+            .map_or(row.len(), |len| start + len);
+
+        let text = row[start..end].iter()
+            .map(|cell| escape(cell.ch))
+            .collect::<String>();
+
+        let attribute = if options.classes {
+            span_classes(&style, &options.theme).map(|classes| format!("class=\"{}\"", classes))
+        } else {
+            span_style(&style, &options.theme).map(|style| format!("style=\"{}\"", style))
+        };
+
+        match attribute {
+            Some(attribute) => line.push_str(&format!("<span {}>{}</span>", attribute, text)),
+            None => line.push_str(&text),
+        }
+
+        start = end;
+    }
+    line.push('\n');
+
+    line
+}
+
+/// Get the inline style of a cell, `None` if it has default style
+fn span_style(cell: &Cell, theme: &Theme) -> Option<String> {
+    let mut declarations: Vec<String> = vec![];
+
+    if let Some(color) = cell.fg {
+        declarations.push(format!("color:#{}", hex_color(color, theme)));
+    }
+    if let Some(color) = cell.bg {
+        declarations.push(format!("background-color:#{}", hex_color(color, theme)));
+    }
+    if cell.attrs.bold {
+        declarations.push(String::from("font-weight:bold"));
+    }
+    if cell.attrs.underline {
+        declarations.push(String::from("text-decoration:underline"));
+    }
+    if cell.attrs.blink {
+        declarations.push(String::from("animation:ansinator-blink 1s step-end infinite"));
+    }
+
+    if declarations.is_empty() {
+        None
+    } else {
+        Some(declarations.join(";"))
+    }
+}
+
+/// Get the classes of a cell, `None` if it has default style
+fn span_classes(cell: &Cell, theme: &Theme) -> Option<String> {
+    let mut classes: Vec<String> = vec![];
+
+    if let Some(color) = cell.fg {
+        classes.push(format!("f-{}", hex_color(color, theme)));
+    }
+    if let Some(color) = cell.bg {
+        classes.push(format!("b-{}", hex_color(color, theme)));
+    }
+    if cell.attrs.bold {
+        classes.push(String::from("bold"));
+    }
+    if cell.attrs.underline {
+        classes.push(String::from("underline"));
+    }
+    if cell.attrs.blink {
+        classes.push(String::from("blink"));
+    }
+
+    if classes.is_empty() {
+        None
+    } else {
+        Some(classes.join(" "))
+    }
+}
+
+/// Get a color as hexadecimal RGB without leading `#`, terminal colors as given by `theme`
+pub(crate) fn hex_color(color: CellColor, theme: &Theme) -> String {
+    let (r, g, b) = color.themed_rgb(theme);
+    hex(r, g, b)
+}
+
+fn hex(r: u8, g: u8, b: u8) -> String {
+    format!("{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Escape the characters with special meaning in HTML and XML
+pub(crate) fn escape(ch: char) -> String {
+    match ch {
+        '&' => String::from("&amp;"),
+        '<' => String::from("&lt;"),
+        '>' => String::from("&gt;"),
+        '"' => String::from("&quot;"),
+        _ => ch.to_string(),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::{Canvas, CellAttributes};
+
+    fn setup_canvas() -> Canvas {
+        let mut canvas = Canvas::new(4, 2);
+        let attrs = CellAttributes { bold: true, .. Default::default() };
+        let red = Cell { ch: '<', fg: Some(CellColor::Rgb(255,0,0)), bg: None, attrs };
+
+        canvas.set(0, 0, red);
+        canvas.set(1, 0, Cell { ch: '&', .. red });
+        canvas.set(2, 0, Cell { bg: Some(CellColor::Fixed(12)), .. red });
+        canvas.set(3, 0, Cell::new('a'));
+
+        canvas
+    }
+
+    #[test]
+    fn test_inline_fragment() {
+        let html = setup_canvas().to_html(&HtmlOptions::new());
+
+        assert!(!html.contains("<html>"));
+        assert!(!html.contains("<style>"));
+        assert!(html.contains(concat!(
+            "<pre class=\"ansinator\" style=\"font-family:monospace;line-height:1\">",
+            "<span style=\"color:#ff0000;font-weight:bold\">&lt;&amp;</span>",
+            "<span style=\"color:#ff0000;background-color:#0000ff;font-weight:bold\">&lt;</span>",
+            "a\n",
+            "    \n",
+            "</pre>\n"
+        )));
+    }
+
+    #[test]
+    fn test_classes_standalone() {
+        let html = setup_canvas().to_html(&HtmlOptions::new().standalone().classes());
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.ends_with("</html>\n"));
+        assert!(html.contains(".f-ff0000 { color: #ff0000; }"));
+        assert!(html.contains(".b-0000ff { background-color: #0000ff; }"));
+        assert!(html.contains("<span class=\"f-ff0000 bold\">&lt;&amp;</span>"));
+        assert!(html.contains("<span class=\"f-ff0000 b-0000ff bold\">&lt;</span>"));
+    }
+
+    #[test]
+    fn test_theme() {
+        let theme = Theme::from_colors(&[(0,0,0); 12].into_iter().chain([(38,139,210)]).collect::<Vec<_>>());
+
+        let html = setup_canvas().to_html(&HtmlOptions::new().theme(&theme));
+        assert!(html.contains("<span style=\"color:#ff0000;background-color:#268bd2;font-weight:bold\">&lt;</span>"));
+
+        let html = setup_canvas().to_html(&HtmlOptions::new().classes().theme(&theme));
+        assert!(html.contains(".b-268bd2 { background-color: #268bd2; }"));
+        assert!(!html.contains("0000ff"));
+    }
+}
@@ -1,13 +1,26 @@
 //! ansi_image provides. 
 //!
 //! + AnsiImage: A general representation of an image in ansi.
-//!
+//! + Canvas: A grid of cells resulting of a convertion.
+//! + SgrEncoder: An encoder of a canvas into minimal ansi escape sequences.
+//! + HtmlOptions: Options of the HTML serialization of a canvas.
+//! + SvgOptions: Options of the SVG serialization of a canvas.
+//! + RasterOptions: Options of the raster rendering of a canvas.
+//! + BlockGlyph: Geometry of unicode block characters.
+//! + octant: Unicode block octant characters.
 //!
 //! + AnsiAscii: A representation of an image in ascii.
-//! + AnsiBlock: A representation of an image in unicode half-block characters and spaces.
+//! + AnsiBlock: A representation of an image in unicode half-block, quadrant or octant characters and spaces.
 //! + AnsiBraile: A representation of an image in 8-dot Braile.
-//! + AnsiUniblock: A representation of an image in unicode sextant characters.
+//! + AnsiUniblock: A representation of an image in unicode sextant characters, or quadrants when fitting colors.
 pub mod ansi;
+pub mod canvas;
+pub mod sgr;
+pub mod html;
+pub mod svg;
+pub mod raster;
+pub mod glyph;
+pub mod octant;
 pub mod ascii;
 pub mod braile;
 pub mod block;
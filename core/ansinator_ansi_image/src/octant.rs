@@ -0,0 +1,115 @@
+//! Unicode block octant characters.
+//!
+//! Octants split a cell in 2x4 sub-cells like braile 8-dot, but painted as
+//! solid blocks. They are the 230 characters from U+1CD00 of the Symbols for
+//! Legacy Computing Supplement (Unicode 16), the remaining 26 patterns reusing
+//! space, full, half, quadrant and other block characters.
+
+use image::GrayImage;
+
+/// Patterns drawn by a previous block character, with their character
+const REUSED: [(u8, char); 26] = [
+    (0x00, ' '),
+    (0x01, '\u{1CEA8}'),
+    (0x02, '\u{1CEAB}'),
+    (0x03, '\u{1FB82}'),
+    (0x05, '\u{2598}'),
+    (0x0A, '\u{259D}'),
+    (0x0F, '\u{2580}'),
+    (0x14, '\u{1FBE6}'),
+    (0x28, '\u{1FBE7}'),
+    (0x3F, '\u{1FB85}'),
+    (0x40, '\u{1CEA3}'),
+    (0x50, '\u{2596}'),
+    (0x55, '\u{258C}'),
+    (0x5A, '\u{259E}'),
+    (0x5F, '\u{259B}'),
+    (0x80, '\u{1CEA0}'),
+    (0xA0, '\u{2597}'),
+    (0xA5, '\u{259A}'),
+    (0xAA, '\u{2590}'),
+    (0xAF, '\u{259C}'),
+    (0xC0, '\u{2582}'),
+    (0xF0, '\u{2584}'),
+    (0xF5, '\u{2599}'),
+    (0xFA, '\u{259F}'),
+    (0xFC, '\u{2586}'),
+    (0xFF, '\u{2588}'),
+];
+
+/// First octant character
+const BASE: u32 = 0x1CD00;
+
+/// Perform a window analysis on the image to determine appropiate octant character
+///
+/// Read the image with a 2x4 window starting on the
+/// top-left coord (x,y)
+///
+/// The octant represent each variation with the
+/// following numbering
+///
+/// | C0| C1|
+/// |---|---|
+/// | 1 | 2 |
+/// | 3 | 4 |
+/// | 5 | 6 |
+/// | 7 | 8 |
+///
+/// Each position represents a bit in a byte in little-endian order.
+///
+pub fn window_analysis(luma: &GrayImage, x: u32, y: u32) -> char {
+    let mut mask = 0;
+    for row in 0..4 {
+        for column in 0..2 {
+            mask |= (luma.get_pixel(x + column, y + row)[0] / 255) << (row * 2 + column);
+        }
+    }
+
+    get_octant(mask)
+}
+
+/// Get the block character of an octant mask, see [`window_analysis`]
+///
+/// The octant characters follow the increasing masks, skipping the reused patterns.
+pub fn get_octant(mask: u8) -> char {
+    match REUSED.binary_search_by_key(&mask, |&(reused, _)| reused) {
+        Ok(i) => REUSED[i].1,
+        Err(i) => std::char::from_u32(BASE + mask as u32 - i as u32).unwrap(),
+    }
+}
+
+/// Get the mask of a character drawn by [`get_octant`], `None` for other characters
+pub fn octant_mask(ch: char) -> Option<u8> {
+    if let Some(&(mask, _)) = REUSED.iter().find(|&&(_, reused)| reused == ch) {
+        return Some(mask);
+    }
+    let index = (ch as u32).checked_sub(BASE)?;
+    (0..=255u8).filter(|&mask| REUSED.binary_search_by_key(&mask, |&(reused, _)| reused).is_err())
+        .nth(index as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_octants() {
+        /* Every mask has its own character */
+        let mut chars: Vec<char> = (0..=255u8).map(get_octant).collect();
+        chars.sort_unstable();
+        chars.dedup();
+        assert_eq!(chars.len(), 256);
+
+        assert_eq!(get_octant(0x04), '\u{1CD00}');
+        assert_eq!(get_octant(0xFE), '\u{1CDE5}');
+        assert_eq!(get_octant(0x0F), '\u{2580}');
+        assert_eq!(get_octant(0x55), '\u{258C}');
+
+        /* Characters go back to their mask */
+        for mask in 0..=255u8 {
+            assert_eq!(octant_mask(get_octant(mask)), Some(mask));
+        }
+        assert_eq!(octant_mask('\u{1CDE6}'), None);
+        assert_eq!(octant_mask('a'), None);
+    }
+}
@@ -0,0 +1,239 @@
+//! Raster rendering of a convertion result.
+//!
+//! Paints the cells of a canvas into an RGB image, using the built-in 5x7
+//! ascii font for ascii characters and procedural shapes for braile dots
+//! and block characters, so no font is needed.
+//!
+//! Each cell is 6x12 pixels before scaling, close to the aspect ratio
+//! of a terminal cell.
+
+use crate::ansi::AnsiImageResult;
+use crate::canvas::Cell;
+use crate::error::AnsiImageError;
+use crate::glyph::BlockGlyph;
+use ansinator_ascii_font::ASCII_FONT;
+use ansinator_terminal_colors::Theme;
+use image::{ColorType, ImageEncoder, Rgb, RgbImage};
+use image::codecs::png::PngEncoder;
+use image::imageops::{self, FilterType};
+use std::io::Write;
+
+/// Width of a cell in pixels before scaling
+pub const CELL_WIDTH: u32 = 6;
+/// Height of a cell in pixels before scaling
+pub const CELL_HEIGHT: u32 = 12;
+
+/// Rows above the 5x7 ascii font glyphs
+const FONT_TOP: u32 = 2;
+
+/// Options of the raster rendering
+///
+/// By default cells are not scaled and the terminal default colors are
+/// white foreground over black background, and terminal colors take their
+/// xterm value unless a theme is given. The blink attribute is ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RasterOptions {
+    scale: u32,
+    foreground: (u8, u8, u8),
+    background: (u8, u8, u8),
+    theme: Theme,
+}
+
+impl Default for RasterOptions {
+    fn default() -> Self {
+        Self { scale: 1, foreground: (255, 255, 255), background: (0, 0, 0), theme: Theme::default() }
+    }
+}
+
+impl RasterOptions {
+    /// Create the default options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the integer scale factor of each cell
+    pub fn scale(&self, scale: u32) -> Self {
+        Self { scale: scale.max(1), .. self.clone() }
+    }
+
+    /// Set the color used by cells without foreground color
+    pub fn default_foreground(&self, rgb: (u8, u8, u8)) -> Self {
+        Self { foreground: rgb, .. self.clone() }
+    }
+
+    /// Set the color used by cells without background color
+    pub fn default_background(&self, rgb: (u8, u8, u8)) -> Self {
+        Self { background: rgb, .. self.clone() }
+    }
+
+    /// Set the theme giving the RGB value of terminal colors
+    pub fn theme(&self, theme: &Theme) -> Self {
+        Self { theme: theme.clone(), .. self.clone() }
+    }
+}
+
+impl AnsiImageResult {
+    /// Paint the cells into an RGB image
+    pub fn to_rgb_image(&self, options: &RasterOptions) -> RgbImage {
+        let mut image = RgbImage::new(self.width * CELL_WIDTH, self.height * CELL_HEIGHT);
+
+        for (y, row) in self.rows().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                paint_cell(&mut image, x as u32 * CELL_WIDTH, y as u32 * CELL_HEIGHT, cell, options);
+            }
+        }
+
+        if options.scale > 1 {
+            imageops::resize(&image,
+                             image.width() * options.scale,
+                             image.height() * options.scale,
+                             FilterType::Nearest)
+        } else {
+            image
+        }
+    }
+
+    /// Write the raster representation to `writer` as PNG
+    pub fn write_png<W: Write>(&self, writer: W, options: &RasterOptions) -> Result<(), AnsiImageError> {
+        let image = self.to_rgb_image(options);
+
+        match PngEncoder::new(writer).write_image(&image, image.width(), image.height(), ColorType::Rgb8) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(AnsiImageError::ImageError(e)),
+        }
+    }
+}
+
+/// Paint a cell with top-left corner at `(x, y)`
+fn paint_cell(image: &mut RgbImage, x: u32, y: u32, cell: &Cell, options: &RasterOptions) {
+    let (r, g, b) = cell.fg.map_or(options.foreground, |color| color.themed_rgb(&options.theme));
+    let fg = Rgb([r, g, b]);
+    let (r, g, b) = cell.bg.map_or(options.background, |color| color.themed_rgb(&options.theme));
+    let bg = Rgb([r, g, b]);
+
+    fill(image, x, y, CELL_WIDTH, CELL_HEIGHT, bg);
+
+    if let Some(glyph) = BlockGlyph::from(cell.ch) {
+        /* Integer bounds so the sub-cells of neighbouring cells abut */
+        let column_bound = |c: u32| c * CELL_WIDTH / glyph.columns;
+        let row_bound = |r: u32| r * CELL_HEIGHT / glyph.rows;
+
+        for r in 0..glyph.rows {
+            for c in 0..glyph.columns {
+                if glyph.is_set(c, r) {
+                    fill(image,
+                         x + column_bound(c), y + row_bound(r),
+                         column_bound(c + 1) - column_bound(c), row_bound(r + 1) - row_bound(r),
+                         fg);
+                }
+            }
+        }
+    }
+    else if ('\u{2800}'..='\u{28FF}').contains(&cell.ch) {
+        /* Braile dots numbered as in braile::window_analysis */
+        const DOTS: [(u32, u32); 8] = [(0,0), (0,1), (0,2), (1,0), (1,1), (1,2), (0,3), (1,3)];
+        let pattern = cell.ch as u32 - 0x2800;
+
+        for (bit, (c, r)) in DOTS.iter().enumerate() {
+            if pattern >> bit & 1 == 1 {
+                fill(image, x + 1 + c * 3, y + r * 3, 2, 2, fg);
+            }
+        }
+    }
+    else if (' '..='~').contains(&cell.ch) {
+        let columns = ASCII_FONT[cell.ch as usize - 32];
+        /* Bold doubles every column one pixel to the right */
+        let thickness = if cell.attrs.bold { 2 } else { 1 };
+
+        for (c, column) in columns.iter().enumerate() {
+            for r in 0..7 {
+                if column >> r & 1 == 1 {
+                    fill(image, x + c as u32, y + FONT_TOP + r, thickness, 1, fg);
+                }
+            }
+        }
+    }
+
+    if cell.attrs.underline {
+        fill(image, x, y + CELL_HEIGHT - 1, CELL_WIDTH, 1, fg);
+    }
+}
+
+/// Fill a rectangle, clipped to the cell grid
+fn fill(image: &mut RgbImage, x: u32, y: u32, width: u32, height: u32, color: Rgb<u8>) {
+    for py in y..(y + height).min(image.height()) {
+        for px in x..(x + width).min(image.width()) {
+            image.put_pixel(px, py, color);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::{Canvas, CellAttributes, CellColor};
+
+    fn setup_canvas() -> Canvas {
+        let mut canvas = Canvas::new(4, 1);
+        let fg = Some(CellColor::Rgb(255, 0, 0));
+        let bg = Some(CellColor::Fixed(12));
+
+        /* Ascii, braile dots 1 and 8, upper half block and left half block */
+        canvas.set(0, 0, Cell { ch: 'I', fg, .. Cell::default() });
+        canvas.set(1, 0, Cell { ch: '\u{2881}', fg, bg, attrs: CellAttributes::default() });
+        canvas.set(2, 0, Cell { ch: '\u{2580}', fg, bg, attrs: CellAttributes::default() });
+        canvas.set(3, 0, Cell { ch: '\u{258C}', fg: None, bg, attrs: CellAttributes { underline: true, .. Default::default() } });
+
+        canvas
+    }
+
+    #[test]
+    fn test_paint_cells() {
+        let image = setup_canvas().to_rgb_image(&RasterOptions::new());
+        let (red, blue, white, black) = (Rgb([255,0,0]), Rgb([0,0,255]), Rgb([255,255,255]), Rgb([0,0,0]));
+
+        assert_eq!(image.dimensions(), (4 * CELL_WIDTH, CELL_HEIGHT));
+
+        /* 'I' has its middle column fully set */
+        assert_eq!(*image.get_pixel(2, FONT_TOP), red);
+        assert_eq!(*image.get_pixel(2, FONT_TOP + 6), red);
+        assert_eq!(*image.get_pixel(0, FONT_TOP + 3), black);
+
+        /* Braile top-left and bottom-right dots */
+        assert_eq!(*image.get_pixel(6 + 1, 0), red);
+        assert_eq!(*image.get_pixel(6 + 4, 10), red);
+        assert_eq!(*image.get_pixel(6 + 4, 0), blue);
+
+        /* Upper half block */
+        assert_eq!(*image.get_pixel(12, 5), red);
+        assert_eq!(*image.get_pixel(12, 6), blue);
+
+        /* Left half block with default foreground and underline */
+        assert_eq!(*image.get_pixel(18 + 2, 3), white);
+        assert_eq!(*image.get_pixel(18 + 3, 3), blue);
+        assert_eq!(*image.get_pixel(18 + 5, CELL_HEIGHT - 1), white);
+
+        /* Terminal colors of the theme */
+        let theme = Theme::from_colors(&[(0,0,0); 12].into_iter().chain([(38,139,210)]).collect::<Vec<_>>());
+        let image = setup_canvas().to_rgb_image(&RasterOptions::new().theme(&theme));
+        assert_eq!(*image.get_pixel(12, 5), red);
+        assert_eq!(*image.get_pixel(12, 6), Rgb([38,139,210]));
+    }
+
+    #[test]
+    fn test_scale_and_png() {
+        let canvas = setup_canvas();
+        let image = canvas.to_rgb_image(&RasterOptions::new());
+        let scaled = canvas.to_rgb_image(&RasterOptions::new().scale(3));
+
+        assert_eq!(scaled.dimensions(), (image.width() * 3, image.height() * 3));
+        assert_eq!(scaled.get_pixel(12 * 3 + 2, 5 * 3 + 2), image.get_pixel(12, 5));
+
+        let mut png: Vec<u8> = vec![];
+        canvas.write_png(&mut png, &RasterOptions::new()).unwrap();
+        let decoded = image::load_from_memory(&png).unwrap().to_rgb8();
+
+        assert_eq!(decoded, image);
+    }
+}
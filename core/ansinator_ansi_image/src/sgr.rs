@@ -0,0 +1,232 @@
+//! Minimal SGR (Select Graphic Rendition) escape sequence encoder.
+//!
+//! Encodes the cells of a canvas keeping track of the current terminal
+//! rendition, so escape sequences are only emitted when the colors or
+//! attributes actually change between neighbouring cells.
+
+use crate::canvas::{Cell, CellAttributes, CellColor};
+
+/// Colors and attributes currently set on the terminal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Rendition {
+    fg: Option<CellColor>,
+    bg: Option<CellColor>,
+    attrs: CellAttributes,
+}
+
+impl Rendition {
+    fn from(cell: &Cell) -> Self {
+        Self { fg: cell.fg, bg: cell.bg, attrs: cell.attrs }
+    }
+
+    /// Check if going to `next` turns off any color or attribute
+    fn removes_from(&self, next: &Rendition) -> bool {
+        (self.attrs.bold && !next.attrs.bold) ||
+        (self.attrs.underline && !next.attrs.underline) ||
+        (self.attrs.blink && !next.attrs.blink) ||
+        (self.fg.is_some() && next.fg.is_none()) ||
+        (self.bg.is_some() && next.bg.is_none())
+    }
+}
+
+/// Encoder of cells into ansi escape sequences
+///
+/// By default the encoder behaves as `ansi_term` painting, resetting every
+/// attribute whenever one of them is turned off and at the end of each row.
+///
+/// The shortest form turns off only what changed (`22`, `24`, `25`, `39`, `49`)
+/// unless a reset is shorter, writes the first 16 terminal colors with their
+/// short codes (`30`–`37`, `90`–`97`) and, when it makes the next row start
+/// shorter, only resets the background at the end of each row.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SgrEncoder {
+    shortest: bool,
+    current: Rendition,
+    pending_row: bool,
+}
+
+impl SgrEncoder {
+    /// Create an encoder starting from a reset terminal
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the shortest form of every sequence
+    pub fn shortest(&self) -> Self {
+        Self { shortest: true, .. *self }
+    }
+
+    /// Append the cell character to `out`, preceded by the sequence
+    /// changing the current rendition to the cell's one if needed
+    pub fn encode_cell(&mut self, cell: &Cell, out: &mut String) {
+        self.transition(Rendition::from(cell), out);
+        out.push(cell.ch);
+    }
+
+    /// Append a whole row to `out`
+    ///
+    /// The newline ending the row is appended by the next call or by
+    /// [`finish`](Self::finish), once the start of the next row is known.
+    pub fn encode_row(&mut self, row: &[Cell], out: &mut String) {
+        self.end_row(row.first().map(Rendition::from), out);
+
+        for cell in row {
+            self.encode_cell(cell, out);
+        }
+        self.pending_row = true;
+    }
+
+    /// End the last row and append the sequence resetting every color and
+    /// attribute, if any is set
+    pub fn finish(&mut self, out: &mut String) {
+        self.end_row(None, out);
+
+        if self.current != Rendition::default() {
+            out.push_str("\x1b[0m");
+            self.current = Rendition::default();
+        }
+    }
+
+    /// Append the newline ending a row, where `next` is the rendition
+    /// of the first cell of the following row
+    fn end_row(&mut self, next: Option<Rendition>, out: &mut String) {
+        if !self.pending_row {
+            return;
+        }
+        self.pending_row = false;
+
+        /* Avoid background extending to end of line on some terminals */
+        let keep = Rendition { bg: None, .. self.current };
+        let end = match next {
+            Some(next) if self.shortest => {
+                /* Keep foreground and attributes if the next row starts shorter */
+                let (mut kept, mut reset) = (*self, *self);
+                let (mut kept_out, mut reset_out) = (String::new(), String::new());
+                kept.transition(keep, &mut kept_out);
+                kept.transition(next, &mut kept_out);
+                reset.transition(Rendition::default(), &mut reset_out);
+                reset.transition(next, &mut reset_out);
+
+                if kept_out.len() <= reset_out.len() { keep } else { Rendition::default() }
+            },
+            _ => Rendition::default(),
+        };
+        self.transition(end, out);
+        out.push('\n');
+    }
+
+    /// Append the sequence going from the current rendition to `next`
+    fn transition(&mut self, next: Rendition, out: &mut String) {
+        if self.current == next {
+            return;
+        }
+
+        /* Reset everything, then set what is needed */
+        let mut reset = vec![String::from("0")];
+        if next != Rendition::default() {
+            reset.extend(self.codes(&Rendition::default(), &next));
+        }
+        let reset = reset.join(";");
+
+        let codes = if self.shortest {
+            /* Pick whichever is shorter */
+            let changes = self.codes(&self.current, &next).join(";");
+            if changes.len() < reset.len() { changes } else { reset }
+        } else if next == Rendition::default() || self.current.removes_from(&next) {
+            reset
+        } else {
+            self.codes(&self.current, &next).join(";")
+        };
+
+        out.push_str("\x1b[");
+        out.push_str(&codes);
+        out.push('m');
+
+        self.current = next;
+    }
+
+    /// Get the codes changing the rendition `from` into `to`
+    fn codes(&self, from: &Rendition, to: &Rendition) -> Vec<String> {
+        let mut codes: Vec<String> = vec![];
+
+        if from.attrs.bold != to.attrs.bold {
+            codes.push(String::from(if to.attrs.bold { "1" } else { "22" }));
+        }
+        if from.attrs.underline != to.attrs.underline {
+            codes.push(String::from(if to.attrs.underline { "4" } else { "24" }));
+        }
+        if from.attrs.blink != to.attrs.blink {
+            codes.push(String::from(if to.attrs.blink { "5" } else { "25" }));
+        }
+        if from.bg != to.bg {
+            codes.push(match to.bg {
+                Some(color) => self.color_code(color, 40),
+                None => String::from("49"),
+            });
+        }
+        if from.fg != to.fg {
+            codes.push(match to.fg {
+                Some(color) => self.color_code(color, 30),
+                None => String::from("39"),
+            });
+        }
+
+        codes
+    }
+
+    /// Get the code of a color, where `base` is 30 for foreground and 40 for background
+    fn color_code(&self, color: CellColor, base: u8) -> String {
+        match color {
+            CellColor::Fixed(index) if self.shortest && index < 8 => {
+                format!("{}", base + index)
+            },
+            CellColor::Fixed(index) if self.shortest && index < 16 => {
+                format!("{}", base + 60 + index - 8)
+            },
+            CellColor::Fixed(index) => {
+                format!("{};5;{}", base + 8, index)
+            },
+            CellColor::Rgb(r, g, b) => {
+                format!("{};2;{};{};{}", base + 8, r, g, b)
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(encoder: SgrEncoder, cells: &[Cell]) -> String {
+        let mut encoder = encoder;
+        let mut out = String::new();
+        encoder.encode_row(cells, &mut out);
+        encoder.finish(&mut out);
+        out
+    }
+
+    #[test]
+    fn test_same_style_is_emitted_once() {
+        let attrs = CellAttributes { bold: true, .. Default::default() };
+        let cell = Cell { ch: 'x', fg: Some(CellColor::Rgb(1,2,3)), bg: None, attrs };
+
+        let out = encode(SgrEncoder::new().shortest(), &[cell, cell, cell]);
+
+        assert_eq!(out, "\x1b[1;38;2;1;2;3mxxx\x1b[0m\n");
+    }
+
+    #[test]
+    fn test_shortest_removal() {
+        let attrs = CellAttributes { bold: true, underline: true, .. Default::default() };
+        let first = Cell { ch: 'a', fg: Some(CellColor::Fixed(9)), bg: Some(CellColor::Fixed(200)), attrs };
+        let second = Cell { ch: 'b', fg: None, attrs: CellAttributes { bold: true, .. attrs }, .. first };
+        let third = Cell { ch: 'c', attrs: CellAttributes::default(), .. second };
+
+        let out = encode(SgrEncoder::new().shortest(), &[first, second, third]);
+        assert_eq!(out, "\x1b[1;4;48;5;200;91ma\x1b[39mb\x1b[22;24mc\x1b[0m\n");
+
+        let out = encode(SgrEncoder::new(), &[first, second, third]);
+        assert_eq!(out, "\x1b[1;4;48;5;200;38;5;9ma\x1b[0;1;4;48;5;200mb\x1b[0;48;5;200mc\x1b[0m\n");
+    }
+}
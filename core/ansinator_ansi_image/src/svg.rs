@@ -0,0 +1,262 @@
+//! SVG serialization of a convertion result.
+//!
+//! Each cell is laid out on a grid of fixed size cells, backgrounds as
+//! `<rect>` elements and characters as `<text>` elements, or as exact
+//! `<rect>` elements for block characters when requested.
+
+use crate::ansi::AnsiImageResult;
+use crate::canvas::Cell;
+use crate::error::AnsiImageError;
+use crate::glyph::BlockGlyph;
+use crate::html::{escape, hex_color};
+use ansinator_terminal_colors::Theme;
+use std::io::Write;
+
+/// Options of the SVG serialization
+///
+/// By default cells are 8x16 pixels and use the `monospace` font family.
+/// Cells without foreground color use the `currentColor` of the document,
+/// the blink attribute is ignored. Terminal colors take their xterm value
+/// unless a theme is given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SvgOptions {
+    cell_width: u32,
+    cell_height: u32,
+    font_family: String,
+    exact_blocks: bool,
+    theme: Theme,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            cell_width: 8,
+            cell_height: 16,
+            font_family: String::from("monospace"),
+            exact_blocks: false,
+            theme: Theme::default(),
+        }
+    }
+}
+
+impl SvgOptions {
+    /// Create the default options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the size in pixels of each cell
+    pub fn cell_size(&self, width: u32, height: u32) -> Self {
+        Self { cell_width: width.max(1), cell_height: height.max(1), .. self.clone() }
+    }
+
+    /// Set the font family of the characters
+    pub fn font_family(&self, font_family: &str) -> Self {
+        Self { font_family: font_family.to_string(), .. self.clone() }
+    }
+
+    /// Draw block characters (half blocks, quadrants and sextants)
+    /// as exact rectangles instead of text
+    pub fn exact_blocks(&self) -> Self {
+        Self { exact_blocks: true, .. self.clone() }
+    }
+
+    /// Set the theme giving the RGB value of terminal colors
+    pub fn theme(&self, theme: &Theme) -> Self {
+        Self { theme: theme.clone(), .. self.clone() }
+    }
+}
+
+impl AnsiImageResult {
+    /// Get the SVG representation
+    pub fn to_svg(&self, options: &SvgOptions) -> String {
+        let mut buffer: Vec<u8> = vec![];
+        /* Writing to memory never fails */
+        self.write_svg(&mut buffer, options).unwrap();
+
+        String::from_utf8(buffer).unwrap()
+    }
+
+    /// Write the SVG representation to `writer`
+    pub fn write_svg<W: Write + ?Sized>(&self, writer: &mut W, options: &SvgOptions) -> Result<(), AnsiImageError> {
+        let width = self.width * options.cell_width;
+        let height = self.height * options.cell_height;
+
+        let head = format!(
+            concat!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n",
+                "<g font-family=\"{2}\" font-size=\"{3}\" fill=\"currentColor\">\n"
+            ),
+            width,
+            height,
+            escape_str(&options.font_family),
+            options.cell_height,
+        );
+
+        if let Err(e) = writer.write_all(head.as_bytes()) {
+            return Err(AnsiImageError::WriteError(e));
+        }
+
+        for (y, row) in self.rows().enumerate() {
+            let elements = svg_row(row, y as u32, options);
+            if let Err(e) = writer.write_all(elements.as_bytes()) {
+                return Err(AnsiImageError::WriteError(e));
+            }
+        }
+
+        match writer.write_all("</g>\n</svg>\n".as_bytes()) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(AnsiImageError::WriteError(e)),
+        }
+    }
+}
+
+/// Get the elements of the row `y`
+///
+/// Adjacent backgrounds of the same color are merged into one rectangle.
+fn svg_row(row: &[Cell], y: u32, options: &SvgOptions) -> String {
+    let (cw, ch) = (options.cell_width, options.cell_height);
+    let mut elements = String::new();
+
+    /* Backgrounds */
+    let mut start = 0;
+    while start < row.len() {
+        let bg = row[start].bg;
+        let end = row[start..].iter()
+            .position(|cell| cell.bg != bg)
+            .map_or(row.len(), |len| start + len);
+
+        if let Some(color) = bg {
+            elements.push_str(&rect(
+                    start as u32 * cw, y * ch,
+                    (end - start) as u32 * cw, ch,
+                    Some(&hex_color(color, &options.theme))
+                    ));
+        }
+
+        start = end;
+    }
+
+    /* Characters */
+    for (x, cell) in row.iter().enumerate() {
+        let x = x as u32 * cw;
+        let fill = cell.fg.map(|color| hex_color(color, &options.theme));
+
+        match BlockGlyph::from(cell.ch) {
+            Some(glyph) if options.exact_blocks => {
+                elements.push_str(&glyph_rects(&glyph, x, y * ch, options, fill.as_deref()));
+            },
+            _ if cell.ch == ' ' => {},
+            _ => {
+                let mut attributes = String::new();
+                if let Some(fill) = fill {
+                    attributes.push_str(&format!(" fill=\"#{}\"", fill));
+                }
+                if cell.attrs.bold {
+                    attributes.push_str(" font-weight=\"bold\"");
+                }
+                if cell.attrs.underline {
+                    attributes.push_str(" text-decoration=\"underline\"");
+                }
+                /* Baseline at four fifths of the cell */
+                elements.push_str(&format!(
+                        "<text x=\"{}\" y=\"{}\"{}>{}</text>\n",
+                        x, y * ch + ch * 4 / 5, attributes, escape(cell.ch)
+                        ));
+            },
+        }
+    }
+
+    elements
+}
+
+/// Get the rectangles of the set sub-cells of a block character at `(x, y)`,
+/// merging adjacent sub-cells of each sub-row
+fn glyph_rects(glyph: &BlockGlyph, x: u32, y: u32, options: &SvgOptions, fill: Option<&str>) -> String {
+    let (cw, ch) = (options.cell_width, options.cell_height);
+    let mut rects = String::new();
+
+    /* Integer bounds so the sub-cells of neighbouring cells abut */
+    let column_bound = |c: u32| x + c * cw / glyph.columns;
+    let row_bound = |r: u32| y + r * ch / glyph.rows;
+
+    for r in 0..glyph.rows {
+        let mut c = 0;
+        while c < glyph.columns {
+            if !glyph.is_set(c, r) {
+                c += 1;
+                continue;
+            }
+            let start = c;
+            while c < glyph.columns && glyph.is_set(c, r) {
+                c += 1;
+            }
+            rects.push_str(&rect(
+                    column_bound(start), row_bound(r),
+                    column_bound(c) - column_bound(start), row_bound(r + 1) - row_bound(r),
+                    fill
+                    ));
+        }
+    }
+
+    rects
+}
+
+fn rect(x: u32, y: u32, width: u32, height: u32, fill: Option<&str>) -> String {
+    match fill {
+        Some(fill) => format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#{}\"/>\n", x, y, width, height, fill),
+        None => format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>\n", x, y, width, height),
+    }
+}
+
+fn escape_str(text: &str) -> String {
+    text.chars().map(escape).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::{Canvas, CellAttributes, CellColor};
+
+    #[test]
+    fn test_text_and_backgrounds() {
+        let mut canvas = Canvas::new(3, 1);
+        let attrs = CellAttributes { bold: true, underline: true, .. Default::default() };
+        let bg = Some(CellColor::Fixed(12));
+        canvas.set(0, 0, Cell { ch: '<', fg: Some(CellColor::Rgb(255,0,0)), bg, attrs });
+        canvas.set(1, 0, Cell { ch: ' ', fg: None, bg, attrs });
+
+        let svg = canvas.to_svg(&SvgOptions::new().cell_size(10, 20).font_family("Fira Code"));
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"30\" height=\"20\""));
+        assert!(svg.contains("<g font-family=\"Fira Code\" font-size=\"20\""));
+        assert!(svg.contains("<rect x=\"0\" y=\"0\" width=\"20\" height=\"20\" fill=\"#0000ff\"/>"));
+        assert!(svg.contains("<text x=\"0\" y=\"16\" fill=\"#ff0000\" font-weight=\"bold\" text-decoration=\"underline\">&lt;</text>"));
+        assert_eq!(svg.matches("<text").count(), 1);
+
+        /* Terminal colors of the theme */
+        let theme = Theme::from_colors(&[(0,0,0); 12].into_iter().chain([(38,139,210)]).collect::<Vec<_>>());
+        let svg = canvas.to_svg(&SvgOptions::new().cell_size(10, 20).theme(&theme));
+        assert!(svg.contains("<rect x=\"0\" y=\"0\" width=\"20\" height=\"20\" fill=\"#268bd2\"/>"));
+    }
+
+    #[test]
+    fn test_exact_blocks() {
+        let mut canvas = Canvas::new(2, 1);
+        let fg = Some(CellColor::Rgb(1,2,3));
+        /* Upper half block and sextant with the left column and bottom right set */
+        canvas.set(0, 0, Cell { ch: '\u{2580}', fg, .. Cell::default() });
+        canvas.set(1, 0, Cell { ch: '\u{1FB32}', fg, .. Cell::default() });
+
+        let options = SvgOptions::new().cell_size(8, 12);
+        assert_eq!(canvas.to_svg(&options).matches("<text").count(), 2);
+
+        let svg = canvas.to_svg(&options.exact_blocks());
+        assert_eq!(svg.matches("<text").count(), 0);
+        assert!(svg.contains("<rect x=\"0\" y=\"0\" width=\"8\" height=\"6\" fill=\"#010203\"/>"));
+        assert!(svg.contains("<rect x=\"8\" y=\"0\" width=\"4\" height=\"4\" fill=\"#010203\"/>"));
+        assert!(svg.contains("<rect x=\"8\" y=\"4\" width=\"4\" height=\"4\" fill=\"#010203\"/>"));
+        assert!(svg.contains("<rect x=\"8\" y=\"8\" width=\"8\" height=\"4\" fill=\"#010203\"/>"));
+    }
+}
@@ -1,16 +1,20 @@
 //! Representation of an image in uniblock.
 #![allow(dead_code, unused)]
 
-use crate::ansi::{AnsiImage, AnsiImageResult, Ansinator};
+use crate::ansi::{self, AnsiImage, AnsiImageResult, Ansinator, Renderer};
+use crate::canvas::{Canvas, Cell, CellColor};
 use crate::error::AnsiImageError;
-use ansinator_image_binarize::Threshold;
-use image::{DynamicImage, GrayImage};
+use crate::octant;
+use ansinator_image_binarize::{AdaptiveThreshold, Dither, Dithering, Threshold, ThresholdMethod};
+use image::{DynamicImage, GrayImage, Pixel, Rgb, RgbImage};
 use std::default::Default;
-use ansi_term::Color;
+use std::io::{Read, Seek};
 
 #[derive(Debug, Clone, Copy)]
 pub enum UniblockColor {
-    Fixed
+    Truecolor,
+    Terminalcolor,
+    Fixed,
 }
 
 impl Default for UniblockColor {
@@ -23,6 +27,10 @@ impl Default for UniblockColor {
 pub enum UniblockMode {
     ManualThreshold,
     OtsuThreshold,
+    AutoThreshold(ThresholdMethod),
+    Dither(Dithering),
+    AdaptiveThreshold(AdaptiveThreshold),
+    ColorFit(FitGlyphs),
 }
 
 impl Default for UniblockMode {
@@ -31,70 +39,199 @@ impl Default for UniblockMode {
    }
 }
 
+/// Glyphs searched by the two color fitting
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FitGlyphs {
+    /// 2x3 sextants, 64 partitions
+    #[default]
+    Sextant,
+    /// 2x2 quadrants, 16 partitions
+    Quadrant,
+    /// 2x4 octants, 256 partitions
+    Octant,
+}
+
+impl FitGlyphs {
+    /// Get the pixels of a cell
+    pub fn scale(&self) -> (u32, u32) {
+        match self {
+            FitGlyphs::Sextant => (2,3),
+            FitGlyphs::Quadrant => (2,2),
+            FitGlyphs::Octant => (2,4),
+        }
+    }
+
+    /// Get the character of a partition, see [`fit_window`]
+    pub fn glyph(&self, mask: u8) -> char {
+        match self {
+            FitGlyphs::Sextant => get_sextant(mask),
+            FitGlyphs::Quadrant => get_quadrant(mask),
+            FitGlyphs::Octant => octant::get_octant(mask),
+        }
+    }
+}
+
 pub type AnsiUniblock = AnsiImage<UniblockMode, UniblockColor>;
 
 impl AnsiUniblock {
     pub fn threshold(&self, value: u8) -> Self {
-        Self { mode: UniblockMode::ManualThreshold, has_threshold: true, threshold: value, scale: (2,3), .. *self}
+        Self { mode: UniblockMode::ManualThreshold, has_threshold: true, threshold: value, scale: (2,3), .. self.clone()}
     }
     pub fn otsu_threshold(&self) -> Self {
-        Self { mode: UniblockMode::OtsuThreshold, scale: (2,3), .. *self}
+        Self { mode: UniblockMode::OtsuThreshold, scale: (2,3), .. self.clone()}
     } 
+    /// Binarize with the threshold of an automatic global method
+    pub fn auto_threshold(&self, method: ThresholdMethod) -> Self {
+        Self { mode: UniblockMode::AutoThreshold(method), scale: (2,3), .. self.clone()}
+    }
+    /// Binarize with error diffusion or ordered dithering
+    pub fn dither(&self, dithering: Dithering) -> Self {
+        Self { mode: UniblockMode::Dither(dithering), scale: (2,3), .. self.clone()}
+    }
+    /// Binarize with the local threshold of the window around each pixel
+    pub fn adaptive_threshold(&self, adaptive: AdaptiveThreshold) -> Self {
+        Self { mode: UniblockMode::AdaptiveThreshold(adaptive), scale: (2,3), .. self.clone()}
+    }
 
-    pub fn get_color(&self) -> ansi_term::Style {
-        let (r,g,b) = self.foreground;
-        let (br,bg,bb) = self.background;
+    /// Choose for each cell the glyph and the pair of colors closest to its pixels,
+    /// instead of binarizing the image
+    pub fn color_fit(&self, glyphs: FitGlyphs) -> Self {
+        Self { mode: UniblockMode::ColorFit(glyphs), scale: glyphs.scale(), .. self.clone()}
+    }
+
+    /// Coloring with true color (RGB8)
+    pub fn true_color(&self) -> Self {
+        Self { color: UniblockColor::Truecolor, .. self.clone()}
+    }
+    /// Coloring with terminal colors (256 terminal color)
+    pub fn terminal_color(&self) -> Self {
+        Self { color: UniblockColor::Terminalcolor, .. self.clone()}
+    }
+
+    /// Get the colors of a cell from the mean of its set pixels (foreground)
+    /// and of its unset pixels (background) for the current coloring
+    pub fn get_color(&self, foreground: (u8,u8,u8), background: (u8,u8,u8)) -> (Option<CellColor>, Option<CellColor>) {
+        let (r, g, b) = foreground;
+        let (br, bg, bb) = background;
         match self.color {
+            UniblockColor::Truecolor => {
+                (Some(CellColor::Rgb(r,g,b)), Some(CellColor::Rgb(br,bg,bb)))
+            },
+            UniblockColor::Terminalcolor => {
+                let frgd_index = self.term_color(r, g, b);
+                let bkgd_index = self.term_color(br, bg, bb);
+                (Some(CellColor::Fixed(frgd_index)), Some(CellColor::Fixed(bkgd_index)))
+            },
             UniblockColor::Fixed => {
-                match (self.has_foreground, self.has_background) {
-                    (false, false) => {
-                        ansi_term::Style::new()
-                    },
-                    (false, true) => {
-                        Color::RGB(0,0,0).on(Color::RGB(br,bg,bb))
-                    },
-                    (true, false) => {
-                        let (r, g, b) = self.foreground;
-                        Color::RGB(r,g,b).normal()
-                    },
-                    (true, true) => {
-                        let (r, g, b) = self.foreground;
-                        let (br, bg, bb) = self.background;
-                        Color::RGB(r,g,b).on(Color::RGB(br,bg,bb))
-                    },
-                }
+                self.fixed_color()
             },
         }
     }
-    pub fn get_style(&self) -> ansi_term::Style {
-        let mut style =  self.get_color();
-        if self.bold {
-            style = style.bold()
-        }
-        if self.blink {
-            style = style.blink()
+    pub fn get_cell(&self, ch: char, foreground: (u8,u8,u8), background: (u8,u8,u8)) -> Cell {
+        let (fg, bg) = self.get_color(foreground, background);
+        self.styled_cell(ch, fg, bg)
+    }
+
+    /// Convert image file to uniblock representation
+    pub fn convert(&self, image_path: &str) -> Result<AnsiImageResult, AnsiImageError> {
+        let image = ansi::open_image(image_path)?;
+        self.convert_image(&image)
+    }
+
+    /// Convert encoded image bytes to uniblock representation
+    pub fn convert_bytes(&self, bytes: &[u8]) -> Result<AnsiImageResult, AnsiImageError> {
+        let image = ansi::load_image_from_memory(bytes)?;
+        self.convert_image(&image)
+    }
+
+    /// Convert an encoded image read from `reader` to uniblock representation
+    pub fn convert_reader<R: Read + Seek>(&self, reader: R) -> Result<AnsiImageResult, AnsiImageError> {
+        let image = ansi::load_image_from_reader(reader)?;
+        self.convert_image(&image)
+    }
+
+    /// Convert a decoded image to uniblock representation
+    pub fn convert_image(&self, image: &DynamicImage) -> Result<AnsiImageResult, AnsiImageError> {
+        self.render(image)
+    }
+
+    /// Convert Gray image to a text representation using ansi (24-bit) true color or 256 terminal colors,
+    /// using sextant characters colored by the mean of the set and unset pixels of the RGB image.
+    fn uniblock(&self, rgb: &RgbImage, luma: GrayImage) -> AnsiImageResult {
+
+        let width = luma.width();
+        let height = luma.height();
+
+        /* Create Result */
+        let (scale_w, scale_h) = self.scale;
+        let mut canvas = Canvas::new(width / scale_w, height / scale_h);
+
+        for y in (0..height).step_by(scale_h as usize) {
+            for x in (0..width).step_by(scale_w as usize) {
+                /* Get window character */
+                let ch = window_analysis(&luma, x, y);
+
+                /* Get mean colors of the set and unset pixels */
+                let (foreground, background) = ansi::window_colors(rgb, &luma, x, y, self.scale);
+
+                /* Convert to appropiate color and style */
+                canvas.set(x / scale_w, y / scale_h, self.get_cell(ch, foreground, background));
+            }
         }
-        if self.underline {
-            style = style.underline()
+       
+        canvas
+    }
+
+    /// Convert RGB image to a text representation using ansi (24-bit) true color or 256 terminal colors,
+    /// fitting the glyph and the colors of each cell to its pixels.
+    fn uniblock_fit(&self, rgb: &RgbImage, glyphs: FitGlyphs) -> AnsiImageResult {
+
+        let width = rgb.width();
+        let height = rgb.height();
+
+        /* Create Result */
+        let (scale_w, scale_h) = self.scale;
+        let mut canvas = Canvas::new(width / scale_w, height / scale_h);
+        let full = ((1u16 << (scale_w * scale_h)) - 1) as u8;
+
+        for y in (0..height).step_by(scale_h as usize) {
+            for x in (0..width).step_by(scale_w as usize) {
+                /* Get best partition and its colors */
+                let (mask, foreground, background) = fit_window(rgb, x, y, self.scale);
+
+                /* Swap the set (brighter) and unset pixels */
+                let (mask, foreground, background) =
+                if self.invert {
+                    (!mask & full, background, foreground)
+                } else {
+                    (mask, foreground, background)
+                };
+
+                /* Convert to appropiate color and style */
+                canvas.set(x / scale_w, y / scale_h, self.get_cell(glyphs.glyph(mask), foreground, background));
+            }
         }
 
-        style
+        canvas
     }
+}
 
-    pub fn convert(&self, image_path: &str) -> Result<AnsiImageResult, AnsiImageError> {
-        
-        /* Try opening the image */
-        let image = match image::open(image_path) {
-            Ok(image) => image,
-            Err(e) => return Err(AnsiImageError::ImageError(e)),
-        };
-    
+impl Renderer for AnsiUniblock {
+    /// Convert a decoded image to uniblock representation
+    fn render(&self, image: &DynamicImage) -> Result<AnsiImageResult, AnsiImageError> {
         /* Resize image to satisfy all internal parameters */
         let image = image.adjust_contrast(self.contrast)
                         .brighten(self.brighten);
         let image = self.image_resize_with_scale(&image);
 
-        /* Cast image to luma */
+        /* Keep the colors of the pixels and cast image to luma */
+        let rgb = image.to_rgb8();
+
+        /* Fit the colors without binarizing */
+        if let UniblockMode::ColorFit(glyphs) = self.mode {
+            return Ok(self.uniblock_fit(&rgb, glyphs));
+        }
+
         let mut luma = image.to_luma8();
 
         /* Binarize */
@@ -104,7 +241,17 @@ impl AnsiUniblock {
             },
             UniblockMode::OtsuThreshold => {
                 luma.otsu_threshold();
-            }
+            },
+            UniblockMode::AutoThreshold(method) => {
+                luma.auto_threshold(method);
+            },
+            UniblockMode::Dither(dithering) => {
+                luma.dither(dithering);
+            },
+            UniblockMode::AdaptiveThreshold(adaptive) => {
+                luma.adaptive_threshold(adaptive);
+            },
+            UniblockMode::ColorFit(_) => {},
         }
         /* Invert colors */
         if self.invert {
@@ -112,37 +259,9 @@ impl AnsiUniblock {
         }
 
         /* Analyze windows and convert */
-        let res = self.uniblock(luma);
+        let res = self.uniblock(&rgb, luma);
         Ok(res)
     }
-
-    /// Convert Gray image to a text representation using ansi (24-bit) true color or 256 terminal colors,
-    /// using sextant characters.
-    fn uniblock<'b>(&self, luma: GrayImage) -> AnsiImageResult<'b> {
-
-        /* Create Result */
-        let mut ansi = AnsiImageResult{ data: vec![] };
-
-        let style = self.get_style();
-        let style_normal = ansi_term::Style::new();
-
-        let width = luma.width();
-        let height = luma.height();
-
-        for y in (0..height).step_by(self.scale.1 as usize) {
-            for x in (0..width).step_by(self.scale.0 as usize) {
-                /* Get window character */
-                let ch = window_analysis(&luma, x, y)
-                            .to_string();
-
-                /* Add ansi */
-                ansi.data.push(style.paint(ch));
-            }
-            ansi.data.push(style_normal.paint("\n"));
-        }
-       
-        ansi
-    }
 }
 
 
@@ -178,11 +297,100 @@ fn window_analysis(win: &GrayImage, x:u32, y:u32) -> char {
     get_sextant(count)
 }
 
+/// Find the partition of a window of `size` starting on the top-left coord (x,y)
+/// in set and unset pixels, along the mean colors of each part, minimizing the
+/// squared color error of the pixels to the mean of their part
+///
+/// Every partition of the at most 8 pixels is searched, each pixel being a bit
+/// of the mask in row-major order. The error of a partition is the sum of the
+/// squared pixels minus `|S|^2 / n` of each part, with `S` the sum of its `n`
+/// pixels, so the best partition maximizes the sum of the latter.
+///
+/// The set part is always the brighter one by luma, so the glyph keeps its shape
+/// without colors. A uniform window is set when brighter than the middle gray,
+/// the part without pixels taking the mean color of the other one.
+pub fn fit_window(rgb: &RgbImage, x: u32, y: u32, size: (u32,u32)) -> (u8, (u8,u8,u8), (u8,u8,u8)) {
+    let (w, h) = size;
+    let pixels: Vec<[u32; 3]> = (0..w * h)
+        .map(|i| {
+            let pixel = rgb.get_pixel(x + i % w, y + i / w);
+            [pixel[0] as u32, pixel[1] as u32, pixel[2] as u32]
+        })
+        .collect();
+    let total = pixels.iter().fold([0u32; 3], |sum, p| [sum[0] + p[0], sum[1] + p[1], sum[2] + p[2]]);
+
+    let score = |sum: [u32; 3], n: u32| -> f64 {
+        if n == 0 {
+            0.0
+        } else {
+            sum.iter().map(|&s| (s as f64).powi(2)).sum::<f64>() / n as f64
+        }
+    };
+
+    let mut best = (0u8, [0u32; 3], 0u32, f64::NEG_INFINITY);
+    for mask in 0..(1u16 << pixels.len()) {
+        let mut set = [0u32; 3];
+        let mut n = 0;
+        for (i, p) in pixels.iter().enumerate() {
+            if mask >> i & 1 == 1 {
+                set = [set[0] + p[0], set[1] + p[1], set[2] + p[2]];
+                n += 1;
+            }
+        }
+        let unset = [total[0] - set[0], total[1] - set[1], total[2] - set[2]];
+
+        let value = score(set, n) + score(unset, pixels.len() as u32 - n);
+        if value > best.3 {
+            best = (mask as u8, set, n, value);
+        }
+    }
+
+    let (mask, set, n, _) = best;
+    let unset = [total[0] - set[0], total[1] - set[1], total[2] - set[2]];
+    let mean = |sum: [u32; 3], n: u32| {
+        let channel = |c: usize| ((sum[c] + n / 2) / n) as u8;
+        (channel(0), channel(1), channel(2))
+    };
+    let count = pixels.len() as u32;
+    let full = ((1u16 << count) - 1) as u8;
+    let luma = |(r, g, b): (u8,u8,u8)| Rgb([r, g, b]).to_luma()[0];
+
+    match n {
+        n if n == 0 || n == count => {
+            let color = mean(total, count);
+            let mask = if luma(color) >= 128 { full } else { 0 };
+            (mask, color, color)
+        },
+        n => {
+            let (foreground, background) = (mean(set, n), mean(unset, count - n));
+            /* Swap the parts so the brighter one is set */
+            if luma(foreground) < luma(background) {
+                (!mask & full, background, foreground)
+            } else {
+                (mask, foreground, background)
+            }
+        },
+    }
+}
+
+/// Get the unicode quadrant block character of the 2x2 window mask
+///
+/// Each position is a bit in row-major order, upper left being the lowest one.
+fn get_quadrant(mask: u8) -> char {
+    const QUADRANTS: [char; 16] = [
+        ' ', '\u{2598}', '\u{259D}', '\u{2580}',
+        '\u{2596}', '\u{258C}', '\u{259E}', '\u{259B}',
+        '\u{2597}', '\u{259A}', '\u{2590}', '\u{259C}',
+        '\u{2584}', '\u{2599}', '\u{259F}', '\u{2588}',
+    ];
+    QUADRANTS[(mask & 0xF) as usize]
+}
+
 /// Get the unicode block sextant character by means of the unicode offset
 ///
 /// The 6-block cell codes start at the base address 0x1FB00
 /// and each variation is an offset from the base address,
-/// but theres no code for empty block nor left block nor right block nor full block
+/// but theres no code for empty block nor left half block nor right half block nor full block
 /// which correspond to offset 0, 21, 42 and 63 respectively
 fn get_sextant(offset: u8) -> char {
     if offset == 0 {
@@ -198,7 +406,7 @@ fn get_sextant(offset: u8) -> char {
         std::char::from_u32(offset as u32 - 22 + 0x1FB14).unwrap()
     }
     else if offset == 42 {
-       '\u{258C}' 
+       '\u{2590}' 
     }
     else if offset > 42 && offset < 63 {
         std::char::from_u32(offset as u32 - 42 + 0x1FB27).unwrap()
@@ -310,4 +518,212 @@ mod tests {
 
         result.save("../uniblock_manual_fixcolor.txt");
     }
+
+    #[test]
+    fn test_convert_bytes_and_reader() {
+        let (w,h) = setup_image_size();
+        let image_path = setup_path();
+
+        let uniblock = AnsiUniblock::new()
+                            .otsu_threshold()
+                            .size(w, h);
+
+        let bytes = std::fs::read(&image_path).unwrap();
+
+        let from_path = uniblock.convert(&image_path).unwrap();
+        let from_bytes = uniblock.convert_bytes(&bytes).unwrap();
+        let from_reader = uniblock.convert_reader(std::io::Cursor::new(&bytes)).unwrap();
+
+        assert_eq!(from_path, from_bytes);
+        assert_eq!(from_path, from_reader);
+    }
+
+    #[test]
+    fn test_dither() {
+        use image::{GrayImage, Luma};
+
+        let (w,h) = setup_image_size();
+        let image_path = setup_path();
+
+        let uniblock = AnsiUniblock::new()
+                            .dither(Dithering::FloydSteinberg)
+                            .size(w, h);
+
+        let result = uniblock.convert(&image_path)
+                            .unwrap();
+
+        result.save("../uniblock_dither.txt");
+
+        /* A uniform midtone mixes set and unset dots instead of a single character */
+        let gray = DynamicImage::ImageLuma8(GrayImage::from_pixel(40, 24, Luma([128])));
+        for dithering in [Dithering::FloydSteinberg, Dithering::Atkinson, Dithering::JarvisJudiceNinke,
+                          Dithering::Sierra, Dithering::Bayer2, Dithering::Bayer4, Dithering::Bayer8] {
+            let result = AnsiUniblock::new()
+                            .dither(dithering)
+                            .size(20, 6)
+                            .convert_image(&gray)
+                            .unwrap();
+
+            assert!(result.cells.iter().all(|cell| cell.ch != ' ' && cell.ch != '\u{2588}'), "{:?}", dithering);
+        }
+    }
+
+    #[test]
+    fn test_adaptive_threshold() {
+        use ansinator_image_binarize::AdaptiveMethod;
+
+        let (w,h) = setup_image_size();
+        let image_path = setup_path();
+
+        for method in [AdaptiveMethod::MeanC, AdaptiveMethod::GaussianC, AdaptiveMethod::Niblack,
+                       AdaptiveMethod::Sauvola, AdaptiveMethod::Bradley] {
+            let uniblock = AnsiUniblock::new()
+                                .adaptive_threshold(AdaptiveThreshold::new(method).window(21))
+                                .size(w, h);
+
+            let result = uniblock.convert(&image_path)
+                                .unwrap();
+
+            assert_eq!((result.width, result.height), (w, h));
+            if method == AdaptiveMethod::Sauvola {
+                result.save("../uniblock_sauvola.txt");
+            }
+        }
+    }
+
+    #[test]
+    fn test_color() {
+        use image::Rgb;
+
+        let (w,h) = setup_image_size();
+        let image_path = setup_path();
+
+        let uniblock = AnsiUniblock::new()
+                            .otsu_threshold()
+                            .true_color()
+                            .size(w, h);
+
+        let result = uniblock.convert(&image_path)
+                            .unwrap();
+
+        result.save("../uniblock_truecolor.txt");
+
+        /* Left column of pixels yellow and right column dark blue */
+        let rgb = RgbImage::from_fn(8, 6, |x, _| if x % 2 == 0 { Rgb([255,255,0]) } else { Rgb([0,0,128]) });
+        let image = DynamicImage::ImageRgb8(rgb);
+        let uniblock = AnsiUniblock::new().threshold(100).size(4, 2);
+
+        let result = uniblock.true_color().convert_image(&image).unwrap();
+        assert!(result.cells.iter().all(|cell| cell.ch == '\u{258C}'));
+        assert!(result.cells.iter().all(|cell| cell.fg == Some(CellColor::Rgb(255,255,0)) && cell.bg == Some(CellColor::Rgb(0,0,128))));
+
+        let result = uniblock.terminal_color().convert_image(&image).unwrap();
+        let (fg, bg) = (uniblock.term_color(255,255,0), uniblock.term_color(0,0,128));
+        assert!(result.cells.iter().all(|cell| cell.fg == Some(CellColor::Fixed(fg)) && cell.bg == Some(CellColor::Fixed(bg))));
+
+        /* Inverted, the dark blue right half is set */
+        let result = uniblock.invert().true_color().convert_image(&image).unwrap();
+        assert!(result.cells.iter().all(|cell| cell.ch == '\u{2590}' && cell.fg == Some(CellColor::Rgb(0,0,128))));
+    }
+
+    #[test]
+    fn test_sextants() {
+        assert_eq!(get_sextant(0), ' ');
+        assert_eq!(get_sextant(1), '\u{1FB00}');
+        assert_eq!(get_sextant(21), '\u{258C}');
+        assert_eq!(get_sextant(22), '\u{1FB14}');
+        assert_eq!(get_sextant(42), '\u{2590}');
+        assert_eq!(get_sextant(62), '\u{1FB3B}');
+        assert_eq!(get_sextant(63), '\u{2588}');
+    }
+
+    #[test]
+    fn test_fit_window() {
+        use image::{imageops::FilterType, Rgb};
+
+        /* Two colors split in a sextant pattern and its complement */
+        let red = Rgb([200,10,10]);
+        let blue = Rgb([10,10,200]);
+        let rgb = RgbImage::from_fn(2, 3, |x, y| if 0b010110 >> (y * 2 + x) & 1 == 1 { red } else { blue });
+        assert_eq!(fit_window(&rgb, 0, 0, (2,3)), (0b010110, (200,10,10), (10,10,200)));
+
+        /* The brighter part is set whatever the order of the search */
+        let rgb = RgbImage::from_fn(2, 3, |x, y| if 0b010110 >> (y * 2 + x) & 1 == 1 { blue } else { red });
+        assert_eq!(fit_window(&rgb, 0, 0, (2,3)), (0b101001, (200,10,10), (10,10,200)));
+
+        /* A uniform window is a space of its color when dark and a full block when bright */
+        let rgb = RgbImage::from_pixel(2, 2, red);
+        assert_eq!(fit_window(&rgb, 0, 0, (2,2)), (0, (200,10,10), (200,10,10)));
+        let rgb = RgbImage::from_pixel(2, 2, Rgb([240,240,240]));
+        assert_eq!(fit_window(&rgb, 0, 0, (2,2)), (0b1111, (240,240,240), (240,240,240)));
+
+        /* The fitting error is never above the one of the binarized partition */
+        let squared_error = |rgb: &RgbImage, x: u32, y: u32, mask: u8, fg: (u8,u8,u8), bg: (u8,u8,u8)| -> i64 {
+            (0..6).map(|i| {
+                let p = rgb.get_pixel(x + i % 2, y + i / 2);
+                let (r, g, b) = if mask >> i & 1 == 1 { fg } else { bg };
+                (p[0] as i64 - r as i64).pow(2) + (p[1] as i64 - g as i64).pow(2) + (p[2] as i64 - b as i64).pow(2)
+            })
+            .sum()
+        };
+        let image = ansi::open_image(&setup_path()).unwrap().resize_exact(40, 30, FilterType::Triangle);
+        let rgb = image.to_rgb8();
+        let mut luma = image.to_luma8();
+        luma.otsu_threshold();
+
+        for y in (0..30).step_by(3) {
+            for x in (0..40).step_by(2) {
+                let mask = (0..6).fold(0, |mask, i| mask | (luma.get_pixel(x + i % 2, y + i / 2)[0] / 255) << i);
+                let (fg, bg) = ansi::window_colors(&rgb, &luma, x, y, (2,3));
+
+                let (fit_mask, fit_fg, fit_bg) = fit_window(&rgb, x, y, (2,3));
+                assert!(squared_error(&rgb, x, y, fit_mask, fit_fg, fit_bg) <= squared_error(&rgb, x, y, mask, fg, bg) + 6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_color_fit_nocolor() {
+        /* Bright left and dark right pixels give the left half block without color */
+        let rgb = RgbImage::from_fn(8, 6, |x, _| if x % 2 == 0 { Rgb([230,220,200]) } else { Rgb([20,30,40]) });
+        let image = DynamicImage::ImageRgb8(rgb);
+
+        for glyphs in [FitGlyphs::Sextant, FitGlyphs::Quadrant] {
+            let result = AnsiUniblock::new().color_fit(glyphs).size(4, 2).convert_image(&image).unwrap();
+            assert!(result.cells.iter().all(|cell| cell.ch == '\u{258C}' && cell.fg.is_none() && cell.bg.is_none()), "{:?}", glyphs);
+
+            let result = AnsiUniblock::new().color_fit(glyphs).invert().size(4, 2).convert_image(&image).unwrap();
+            assert!(result.cells.iter().all(|cell| cell.ch == '\u{2590}'), "{:?}", glyphs);
+        }
+    }
+
+    #[test]
+    fn test_color_fit() {
+        use crate::glyph::BlockGlyph;
+
+        let (w,h) = setup_image_size();
+        let image_path = setup_path();
+
+        for glyphs in [FitGlyphs::Sextant, FitGlyphs::Quadrant, FitGlyphs::Octant] {
+            let uniblock = AnsiUniblock::new()
+                                .color_fit(glyphs)
+                                .true_color()
+                                .size(w, h);
+
+            let result = uniblock.convert(&image_path)
+                                .unwrap();
+
+            assert_eq!((result.width, result.height), (w, h));
+            if glyphs == FitGlyphs::Sextant {
+                result.save("../uniblock_fit.txt");
+            }
+        }
+
+        /* Quadrants follow the row-major order of the block geometry */
+        for mask in 1..15 {
+            let glyph = BlockGlyph::from(get_quadrant(mask)).unwrap();
+            let set = (0..4).fold(0, |set, i| set | (glyph.is_set(i % 2 * glyph.columns / 2, i / 2 * glyph.rows / 2) as u8) << i);
+            assert_eq!(set, mask);
+        }
+    }
 }
@@ -3,6 +3,7 @@
 //! Provides an ascii 5x7 font abstraction, providing:
 //! + Best fitting character 
 //! + Comparing ascii characters
+//! + 5x7 bitmap of the printable ascii characters
 
 /// Short type alias for font data
 type Font = [u8;5*7];
@@ -147,6 +148,8 @@ pub fn maximize_structural_similarity(font1: &AsciiFont, font_set: &Vec<AsciiFon
 /// <https://github.com/adafruit/monochron/blob/master/firmware/font5x7.h>
 /// With a minor changes on last 2 rows
 ///
+/// Indexed by the ascii code of the character minus 32, starting from space.
+///
 /// Each 5x7 ascii character is encoded
 /// as 5 bytes, each byte corresponds to a column ordered from left to right,
 /// and each bit position corresponds to a row value,
@@ -163,7 +166,7 @@ pub fn maximize_structural_similarity(font1: &AsciiFont, font_set: &Vec<AsciiFon
 /// | v6 | w6 | x6 | y6 | z6 |
 /// | v7 | w7 | x7 | y7 | z7 |
 ///
-const ASCII_FONT: [[u8; 5] ; 127-32] = [
+pub const ASCII_FONT: [[u8; 5] ; 127-32] = [
     [0x00, 0x00, 0x00, 0x00, 0x00],// (space)
     [0x00, 0x00, 0x5F, 0x00, 0x00],// !
     [0x00, 0x07, 0x00, 0x07, 0x00],// "
@@ -6,9 +6,14 @@
 //! + Braile
 //! + Block
 //! + Uniblock
+//! + Info
 
 
 use clap::{Args, Parser, Subcommand};
+use ansinator_ansi_image::ascii::DEFAULT_CHAR_SET;
+use ansinator_terminal_colors::Theme;
+use crate::output::parse_palette;
+use crate::binarize::DITHERING_METHODS;
 
 #[derive(Debug, Parser)]
 #[clap(
@@ -35,10 +40,13 @@ pub enum AnsinatorCommands {
     Braile(Braile),
     /// Convert image to unicode blocks
     Uniblock(Uniblock),
+    /// Print image statistics, histogram and threshold suggestions
+    Info(Info),
 }
 
+/// Input and output arguments shared by every program
 #[derive(Debug, Args)]
-pub struct Ascii {
+pub struct IoArgs {
     /// Input image
     pub image: String,
 
@@ -55,6 +63,168 @@ pub struct Ascii {
     )]
     pub noecho: bool,
 
+    /// Select color capability of the output, 8 and 16 use only the basic ansi colors
+    /// [auto detects it from COLORTERM, TERM, NO_COLOR and CLICOLOR_FORCE]
+    #[clap(long,
+           alias = "colors",
+           verbatim_doc_comment,
+           ignore_case = true,
+           help_heading = "COLORING",
+           value_name = "DEPTH",
+           default_value = "auto",
+           value_parser = ["auto", "truecolor", "256", "16", "8", "none"],
+    )]
+    pub color: String,
+
+    /// Select metric to approximate colors on the terminal palettes,
+    /// CIE76 and CIEDE2000 compare colors as perceived [default: rgb]
+    #[clap(long,
+           verbatim_doc_comment,
+           ignore_case = true,
+           help_heading = "COLORING",
+           value_name = "METRIC",
+           default_value = "rgb",
+           hide_default_value = true,
+           value_parser = ["rgb", "redmean", "cie76", "ciede2000"],
+    )]
+    pub metric: String,
+
+    /// Approximate colors with the real colors of the terminal theme, from
+    /// a GIMP palette (.gpl), hex colors list, Xresources or Alacritty colors
+    #[clap(long,
+           verbatim_doc_comment,
+           help_heading = "COLORING",
+           value_name = "FILE",
+           value_parser = parse_palette,
+    )]
+    pub palette: Option<Theme>,
+
+    /// Output only characters, without colors nor styles
+    /// [automatic when stdout is not a terminal, unless CLICOLOR_FORCE is set
+    /// or a color capability is given, or when NO_COLOR is set]
+    #[clap(long,
+           verbatim_doc_comment,
+           help_heading = "OUTPUT FORMAT",
+    )]
+    pub plain: bool,
+
+    /// Strip trailing whitespace of each line of plain output
+    #[clap(long = "strip-trailing",
+           help_heading = "OUTPUT FORMAT",
+    )]
+    pub strip_trailing: bool,
+
+    /// Select output file format
+    /// [otherwise selected by the file extension, .html or .htm for HTML, .svg for SVG, .png for PNG]
+    #[clap(long,
+           verbatim_doc_comment,
+           ignore_case = true,
+           help_heading = "OUTPUT FORMAT",
+           value_name = "FORMAT",
+           value_parser = ["ANSI", "PLAIN", "HTML", "SVG", "PNG"],
+    )]
+    pub format: Option<String>,
+
+    /// Write HTML as a fragment to embed in another page
+    #[clap(long = "html-fragment",
+           help_heading = "OUTPUT FORMAT",
+    )]
+    pub html_fragment: bool,
+
+    /// Style HTML with classes instead of inline styles
+    #[clap(long = "html-classes",
+           help_heading = "OUTPUT FORMAT",
+    )]
+    pub html_classes: bool,
+
+    /// Set SVG cell size in pixels
+    #[clap(long = "svg-cell-size",
+           help_heading = "OUTPUT FORMAT",
+           number_of_values = 2,
+           value_names = &["WIDTH", "HEIGHT"],
+    )]
+    pub svg_cell_size: Vec<u32>,
+
+    /// Set SVG font family
+    #[clap(long = "svg-font",
+           help_heading = "OUTPUT FORMAT",
+           value_name = "FONT FAMILY",
+    )]
+    pub svg_font: Option<String>,
+
+    /// Draw SVG block characters as exact rectangles
+    #[clap(long = "svg-exact-blocks",
+           help_heading = "OUTPUT FORMAT",
+    )]
+    pub svg_exact_blocks: bool,
+
+    /// Set PNG scale factor of each 6x12 pixels cell
+    #[clap(long = "png-scale",
+           help_heading = "OUTPUT FORMAT",
+           value_name = "SCALE",
+           default_value_t = 1,
+    )]
+    pub png_scale: u32,
+}
+
+/// Palette mapping arguments of the modes coloring each pixel
+#[derive(Debug, Args)]
+pub struct ColorMappingArgs {
+    /// Color with an adaptive palette of the N colors best representing the image
+    /// [1-256]
+    #[clap(long,
+           verbatim_doc_comment,
+           help_heading = "COLORING",
+           value_name = "N",
+           value_parser = clap::value_parser!(u16).range(1..=256),
+    )]
+    pub quantize: Option<u16>,
+
+    /// Select method building the adaptive palette, k-means refines
+    /// the median cut colors [default: median-cut]
+    #[clap(long = "quantize-method",
+           verbatim_doc_comment,
+           ignore_case = true,
+           help_heading = "COLORING",
+           value_name = "METHOD",
+           default_value = "median-cut",
+           hide_default_value = true,
+           value_parser = ["median-cut", "kmeans"],
+    )]
+    pub quantize_method: String,
+
+    /// Restrict the adaptive palette to terminal colors
+    /// [automatic with terminal colors or a color capability below true color]
+    #[clap(long = "quantize-terminal",
+           verbatim_doc_comment,
+           help_heading = "COLORING",
+           requires = "quantize",
+    )]
+    pub quantize_terminal: bool,
+
+    /// Dither the colors approximated on the terminal or adaptive palette
+    /// [error diffusion: floyd-steinberg, atkinson, jarvis, sierra]
+    /// [ordered: bayer2, bayer4, bayer8]
+    #[clap(short = 'd',
+           long,
+           verbatim_doc_comment,
+           ignore_case = true,
+           help_heading = "COLORING",
+           value_name = "METHOD",
+           hide_possible_values = true,
+           value_parser = DITHERING_METHODS,
+    )]
+    pub dither: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct Ascii {
+    #[clap(flatten)]
+    pub io: IoArgs,
+
+    #[clap(flatten)]
+    pub mapping: ColorMappingArgs,
+
     /// Use given character set for convertion
     /// (only ascii characters otherwise character is ignored)
     //#[clap(default_value_t = String::from(" .~*:+zM#&@$"))]
@@ -63,7 +233,7 @@ pub struct Ascii {
            verbatim_doc_comment,
            next_line_help = true,
            value_name = "CHAR SET",
-           default_value_t = String::from(" !\"#$%&\'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~")
+           default_value_t = String::from(DEFAULT_CHAR_SET)
     )]
     pub char_set: String,
 
@@ -209,33 +379,31 @@ pub struct Ascii {
 
 #[derive(Debug, Args)]
 pub struct Block {
-    /// Input image
-    pub image: String,
-
-    /// Save convertion to file
-    #[clap(short = 'o',
-           long,
-           value_name = "OUTPUT FILE",
-    )]
-    pub output: Vec<String>,
+    #[clap(flatten)]
+    pub io: IoArgs,
 
-    /// Prevent convertion from printing out to stdout
-    #[clap(short,
-           long,
-    )]
-    pub noecho: bool,
+    #[clap(flatten)]
+    pub mapping: ColorMappingArgs,
 
     /// Select character mode
+    /// [QUADRANT fits the glyph and colors of 2x2 pixels, QUADRANT-MONO draws them uncolored]
+    /// [OCTANT and OCTANT-MONO do the same with 2x4 pixels]
     #[clap(short = 'm',
            long = "mode",
            verbatim_doc_comment,
            ignore_case = true,
            help_heading = "MODE",
            default_value = "HALF",
-           value_parser = ["HALF", "WHOLE", ],
+           value_parser = ["HALF", "WHOLE", "QUADRANT", "QUADRANT-MONO", "OCTANT", "OCTANT-MONO"],
     )]
     pub block_mode: String,
 
+    /// Draw quadrants instead of octants, for fonts without the Unicode 16 octants
+    #[clap(long,
+           help_heading = "MODE",
+    )]
+    pub no_octants: bool,
+
 
     /// Use bold style
     #[clap(short = 'b', long,
@@ -260,6 +428,37 @@ pub struct Block {
     )]
     pub termcolor: bool,
 
+    /// Set foreground color RGB of the monochrome modes
+    /// [0-255 each channel]
+    #[clap(short = 'F',
+           long,
+           verbatim_doc_comment,
+           help_heading = "COLORING",
+           number_of_values = 3,
+           value_names = &["R", "G", "B"],
+    )]
+    pub frgdcolor: Vec<u8>,
+
+    /// Set background color RGB of the monochrome modes
+    /// [0-255 each channel]
+    #[clap(short = 'B',
+           long,
+           verbatim_doc_comment,
+           number_of_values = 3,
+           help_heading = "COLORING",
+           value_names = &["R", "G", "B"],
+    )]
+    pub bkgdcolor: Vec<u8>,
+
+
+    /// Set threshold of the monochrome modes manually [0-255].
+    /// If not set, then Otsu's method is used.
+    #[clap(long = "set-threshold",
+           verbatim_doc_comment,
+           help_heading = "BINARIZATION",
+    )]
+    pub threshold: Option<u8>,
+
 
     /// Invert image colors
     #[clap(short = 'i',
@@ -330,32 +529,90 @@ pub struct Block {
     pub filter: String,
 }
 
+/// Binarization arguments of the modes drawing dots
 #[derive(Debug, Args)]
-pub struct Braile {
-    /// Input image
-    pub image: String,
-
+pub struct BinarizeArgs {
+    /// Set image threshold manually [0-255].
+    /// If not set, then an automatic method is used.
+    #[clap(short = 't',
+           long = "set-threshold",
+           verbatim_doc_comment,
+           help_heading = "BINARIZATION",
+    )]
+    pub threshold: Vec<u8>,
 
-    /// Save convertion to file
-    #[clap(short = 'o',
-           long,
-           value_name = "OUTPUT FILE",
+    /// Binarize with the threshold of an automatic method [default: otsu]
+    /// [otsu, triangle, kapur, yen, isodata, li]
+    #[clap(short = 'A',
+           long = "auto-threshold",
+           verbatim_doc_comment,
+           ignore_case = true,
+           help_heading = "BINARIZATION",
+           value_name = "METHOD",
+           conflicts_with_all = &["threshold", "dither", "adaptive"],
+           hide_possible_values = true,
+           value_parser = ["otsu", "triangle", "kapur", "yen", "isodata", "li"],
     )]
-    pub output: Vec<String>,
+    pub auto_threshold: Option<String>,
 
-    /// Prevent convertion from printing out to stdout
-    #[clap(short,
+    /// Binarize by dithering, spreading the error to keep the midtones
+    /// [error diffusion: floyd-steinberg, atkinson, jarvis, sierra]
+    /// [ordered: bayer2, bayer4, bayer8]
+    #[clap(short = 'd',
            long,
+           verbatim_doc_comment,
+           ignore_case = true,
+           help_heading = "BINARIZATION",
+           value_name = "METHOD",
+           conflicts_with = "threshold",
+           hide_possible_values = true,
+           value_parser = DITHERING_METHODS,
     )]
-    pub noecho: bool,
+    pub dither: Option<String>,
 
-    /// Set image threshold manually [0-255].
-    /// If not set, then Otsu's binarization method is used.
-    #[clap(short = 't',
-           long = "set-threshold",
+    /// Binarize with the local threshold around each pixel
+    /// [mean, gaussian, niblack, sauvola, bradley]
+    #[clap(short = 'a',
+           long,
+           verbatim_doc_comment,
+           ignore_case = true,
+           help_heading = "BINARIZATION",
+           value_name = "METHOD",
+           conflicts_with_all = &["threshold", "dither"],
+           hide_possible_values = true,
+           value_parser = ["mean", "gaussian", "niblack", "sauvola", "bradley"],
+    )]
+    pub adaptive: Option<String>,
+
+    /// Set the size of the window of the adaptive threshold [default: 15]
+    #[clap(long = "adaptive-window",
+           help_heading = "BINARIZATION",
+           value_name = "PIXELS",
+           requires = "adaptive",
+           value_parser = clap::value_parser!(u32).range(3..),
+    )]
+    pub adaptive_window: Option<u32>,
+
+    /// Set the k parameter of the adaptive threshold
+    /// [default: mean 5, gaussian 5, niblack -0.2,
+    /// sauvola 0.34, bradley 0.15]
+    #[clap(long = "adaptive-k",
            verbatim_doc_comment,
+           help_heading = "BINARIZATION",
+           value_name = "K",
+           requires = "adaptive",
+           allow_hyphen_values = true,
     )]
-    pub threshold: Vec<u8>,
+    pub adaptive_k: Option<f64>,
+}
+
+#[derive(Debug, Args)]
+pub struct Braile {
+    #[clap(flatten)]
+    pub io: IoArgs,
+
+    #[clap(flatten)]
+    pub binarize: BinarizeArgs,
 
 
     /// Use bold style
@@ -378,6 +635,7 @@ pub struct Braile {
            verbatim_doc_comment,
            help_heading = "COLORING",
            number_of_values = 3,
+           conflicts_with_all = &["termcolor", "rgbcolor", "mean-background"],
            value_names = &["R", "G", "B"],
     )]
     pub frgdcolor: Vec<u8>,
@@ -389,10 +647,38 @@ pub struct Braile {
            verbatim_doc_comment,
            number_of_values = 3,
            help_heading = "COLORING",
+           conflicts_with_all = &["termcolor", "rgbcolor", "mean-background"],
            value_names = &["R", "G", "B"],
     )]
     pub bkgdcolor: Vec<u8>,
 
+    /// Use true color (24-bit) color space,
+    /// coloring each character with the mean of its set dots
+    #[clap(short,
+           long,
+           verbatim_doc_comment,
+           help_heading = "COLORING",
+           conflicts_with = "termcolor"
+    )]
+    pub rgbcolor: bool,
+
+    /// Use 256 terminal colors (8-bit) color space,
+    /// coloring each character with the mean of its set dots
+    #[clap(long,
+           verbatim_doc_comment,
+           help_heading = "COLORING",
+    )]
+    pub termcolor: bool,
+
+    /// Color the background with the mean of the unset dots
+    /// [colors as the output supports unless --rgbcolor or --termcolor]
+    #[clap(short = 'M',
+           long,
+           verbatim_doc_comment,
+           help_heading = "COLORING",
+    )]
+    pub mean_background: bool,
+
 
     /// Invert image luma colors
     #[clap(short = 'i',
@@ -465,30 +751,24 @@ pub struct Braile {
 
 #[derive(Debug, Args)]
 pub struct Uniblock {
-    /// Input image
-    pub image: String,
+    #[clap(flatten)]
+    pub io: IoArgs,
 
+    #[clap(flatten)]
+    pub binarize: BinarizeArgs,
 
-    /// Save convertion to file
-    #[clap(short = 'o',
-           long,
-           value_name = "OUTPUT FILE",
-    )]
-    pub output: Vec<String>,
-
-    /// Prevent convertion from printing out to stdout
-    #[clap(short,
-           long,
-    )]
-    pub noecho: bool,
-
-    /// Set image threshold manually [0-255].
-    /// If not set, then Otsu's binarization method is used.
-    #[clap(short = 't',
-           long = "set-threshold",
+    /// Choose the glyph and colors of each cell closest to its pixels instead of binarizing
+    /// [sextant, quadrant, octant]
+    #[clap(long,
            verbatim_doc_comment,
+           ignore_case = true,
+           help_heading = "BINARIZATION",
+           value_name = "GLYPHS",
+           conflicts_with_all = &["threshold", "auto-threshold", "dither", "adaptive"],
+           hide_possible_values = true,
+           value_parser = ["sextant", "quadrant", "octant"],
     )]
-    pub threshold: Vec<u8>,
+    pub fit: Option<String>,
 
 
     /// Use bold style
@@ -511,6 +791,7 @@ pub struct Uniblock {
            verbatim_doc_comment,
            help_heading = "COLORING",
            number_of_values = 3,
+           conflicts_with_all = &["termcolor", "rgbcolor"],
            value_names = &["R", "G", "B"],
     )]
     pub frgdcolor: Vec<u8>,
@@ -522,10 +803,29 @@ pub struct Uniblock {
            verbatim_doc_comment,
            number_of_values = 3,
            help_heading = "COLORING",
+           conflicts_with_all = &["termcolor", "rgbcolor"],
            value_names = &["R", "G", "B"],
     )]
     pub bkgdcolor: Vec<u8>,
 
+    /// Use true color (24-bit) color space,
+    /// coloring each character with the mean of its set and unset pixels
+    #[clap(short,
+           long,
+           verbatim_doc_comment,
+           help_heading = "COLORING",
+           conflicts_with = "termcolor"
+    )]
+    pub rgbcolor: bool,
+
+    /// Use 256 terminal colors (8-bit) color space,
+    /// coloring each character with the mean of its set and unset pixels
+    #[clap(long,
+           verbatim_doc_comment,
+           help_heading = "COLORING",
+    )]
+    pub termcolor: bool,
+
 
     /// Invert image luma colors
     #[clap(short = 'i',
@@ -596,3 +896,38 @@ pub struct Uniblock {
     pub filter: String,
 
 }
+
+#[derive(Debug, Args)]
+pub struct Info {
+    /// Input image
+    pub image: String,
+
+    /// Set histogram width in characters
+    /// [each character holds two bins]
+    #[clap(short = 'W',
+           long,
+           verbatim_doc_comment,
+           help_heading = "HISTOGRAM",
+           default_value_t = 64,
+           value_parser = clap::value_parser!(u32).range(1..=128),
+    )]
+    pub width: u32,
+
+    /// Set histogram height in characters
+    #[clap(short = 'H',
+           long,
+           help_heading = "HISTOGRAM",
+           default_value_t = 8,
+           value_parser = clap::value_parser!(u32).range(1..),
+    )]
+    pub height: u32,
+
+    /// Set the number of multi-level Otsu thresholds
+    #[clap(short = 'l',
+           long,
+           help_heading = "THRESHOLDS",
+           default_value_t = 3,
+           value_parser = clap::value_parser!(u32).range(1..=8),
+    )]
+    pub levels: u32,
+}
@@ -9,15 +9,12 @@
 //! + Gradient(unicode) and Pattern(ascii) convertion methods
 
 use crate::args::Ascii;
-use ansinator_ansi_image::{ascii::AnsiAscii, error::AnsiImageError, ansi::Ansinator};
-
-//use std::error::Error;
-
-//type MyResult<T> = Result<T, Box<dyn Error>>;
-type MyResult<T> = Result<T, AnsiImageError>;
+use ansinator_ansi_image::{ascii::AnsiAscii, ansi::{Ansinator, Renderer}};
+use ansinator_terminal_colors::ColorDepth;
 
 impl Ascii {
-    pub fn run(&self) -> MyResult<()> {
+    /// Build the configured renderer
+    pub fn renderer(&self) -> Box<dyn Renderer> {
         let ascii = AnsiAscii::new();
         /* Ansi style */
         
@@ -74,6 +71,34 @@ impl Ascii {
             ascii
         };
 
+        /* Color the image pixels as supported when no color mode is given */
+        let color_depth = self.io.color_depth();
+        let ascii =
+        if self.rgbcolor || self.termcolor || !self.frgdcolor.is_empty() || !self.bkgdcolor.is_empty() {
+            ascii
+        } else {
+            match color_depth {
+                ColorDepth::Truecolor => ascii.true_color(),
+                ColorDepth::Ansi256 | ColorDepth::Ansi16 | ColorDepth::Ansi8 => ascii.terminal_color(),
+                ColorDepth::None => ascii,
+            }
+        };
+        let ascii = ascii.color_depth(color_depth);
+        let ascii = ascii.metric(self.io.metric());
+        let ascii = ascii.theme(self.io.theme());
+        let ascii =
+        if let Some(quantizer) = self.mapping.quantizer(color_depth.palette().is_some()) {
+            ascii.quantize(quantizer)
+        } else {
+            ascii
+        };
+        let ascii =
+        if let Some(dithering) = self.mapping.dithering() {
+            ascii.dither(dithering)
+        } else {
+            ascii
+        };
+
         /* Set size */
         let ascii = 
         if self.fullscreen {
@@ -104,25 +129,9 @@ impl Ascii {
         };
 
 
-        /* Convert image to ascii */
-        //let ansi_output = ascii.convert(&self.image, &self.char_set).unwrap();
-        let ansi_output = match ascii.convert(&self.image, &self.char_set) {
-            Ok(a) => a,
-            Err(e) => return Err(e),
-        };
-
-        /* Print to stdout */
-        if !self.noecho {
-            ansi_output.print();
-        }
-
-        /*Save to output file*/
-        if !self.output.is_empty() {
-            if let Err(e) = ansi_output.save(&self.output[0]) {
-                return Err(e);
-            }
-        }
+        /* Character set */
+        let ascii = ascii.char_set(&self.char_set);
 
-        Ok(())
+        Box::new(ascii)
     }
 }
@@ -0,0 +1,84 @@
+//! Binarization method selection
+//!
+//! Select the binarization of the modes drawing dots, by manual threshold,
+//! automatic global threshold, dithering or local adaptive threshold.
+
+use crate::args::BinarizeArgs;
+use ansinator_image_binarize::{AdaptiveMethod, AdaptiveThreshold, Dithering, ThresholdMethod};
+
+/// Names of the dithering methods
+pub const DITHERING_METHODS: [&str; 7] = ["floyd-steinberg", "atkinson", "jarvis", "sierra", "bayer2", "bayer4", "bayer8"];
+
+/// Get the dithering method of a name, Floyd-Steinberg if unknown
+pub fn dithering_method(name: &str) -> Dithering {
+    match name.to_lowercase().as_str() {
+        "atkinson" => Dithering::Atkinson,
+        "jarvis" => Dithering::JarvisJudiceNinke,
+        "sierra" => Dithering::Sierra,
+        "bayer2" => Dithering::Bayer2,
+        "bayer4" => Dithering::Bayer4,
+        "bayer8" => Dithering::Bayer8,
+        _ => Dithering::FloydSteinberg,
+    }
+}
+
+/// Get the name of an automatic global threshold method
+pub fn threshold_method_name(method: ThresholdMethod) -> &'static str {
+    match method {
+        ThresholdMethod::Otsu => "otsu",
+        ThresholdMethod::Triangle => "triangle",
+        ThresholdMethod::Kapur => "kapur",
+        ThresholdMethod::Yen => "yen",
+        ThresholdMethod::Isodata => "isodata",
+        ThresholdMethod::Li => "li",
+    }
+}
+
+impl BinarizeArgs {
+    /// Get the manual threshold, `None` if not set
+    pub fn threshold(&self) -> Option<u8> {
+        self.threshold.first().copied()
+    }
+
+    /// Get the automatic global threshold method, Otsu's method if not set
+    pub fn threshold_method(&self) -> ThresholdMethod {
+        match self.auto_threshold.as_deref().map(str::to_lowercase).as_deref() {
+            Some("triangle") => ThresholdMethod::Triangle,
+            Some("kapur") => ThresholdMethod::Kapur,
+            Some("yen") => ThresholdMethod::Yen,
+            Some("isodata") => ThresholdMethod::Isodata,
+            Some("li") => ThresholdMethod::Li,
+            _ => ThresholdMethod::Otsu,
+        }
+    }
+
+    /// Get the dithering method, `None` if not dithering
+    pub fn dithering(&self) -> Option<Dithering> {
+        self.dither.as_deref().map(dithering_method)
+    }
+
+    /// Get the local adaptive threshold, `None` if not set
+    pub fn adaptive_threshold(&self) -> Option<AdaptiveThreshold> {
+        let method =
+        match self.adaptive.as_ref()?.to_lowercase().as_str() {
+            "gaussian" => AdaptiveMethod::GaussianC,
+            "niblack" => AdaptiveMethod::Niblack,
+            "sauvola" => AdaptiveMethod::Sauvola,
+            "bradley" => AdaptiveMethod::Bradley,
+            _ => AdaptiveMethod::MeanC,
+        };
+        let adaptive = AdaptiveThreshold::new(method);
+
+        let adaptive =
+        match self.adaptive_window {
+            Some(window) => adaptive.window(window),
+            None => adaptive,
+        };
+        let adaptive =
+        match self.adaptive_k {
+            Some(k) => adaptive.k(k),
+            None => adaptive,
+        };
+        Some(adaptive)
+    }
+}
@@ -4,20 +4,16 @@
 //!
 //! + Half block unicode mode
 //! + Whole block mode
+//! + Quadrant and octant modes, two color fitted or monochrome
 //! + RGB coloring
 //! + 256 Terminal Colors coloring
 
 use crate::args::Block;
-use ansinator_ansi_image::{block::AnsiBlock, ansi::Ansinator};
-use ansinator_ansi_image::error::AnsiImageError;
-
-//use std::error::Error;
-
-//type MyResult<T> = Result<T, Box<dyn Error>>;
-type MyResult<T> = Result<T, AnsiImageError>;
+use ansinator_ansi_image::{block::AnsiBlock, ansi::{Ansinator, Renderer}};
 
 impl Block {
-    pub fn run(&self) -> MyResult<()> {
+    /// Build the configured renderer
+    pub fn renderer(&self) -> Box<dyn Renderer> {
 
         let block = AnsiBlock::new();
         /* Ansi style */
@@ -44,6 +40,44 @@ impl Block {
             block.true_color()
         };
 
+        /* Fixed colors of the monochrome modes */
+        let block =
+        if !self.frgdcolor.is_empty() {
+            let r = self.frgdcolor[0];
+            let g = self.frgdcolor[1];
+            let b = self.frgdcolor[2];
+            block.set_foreground((r,g,b))
+        } else {
+            block
+        };
+        let block =
+        if !self.bkgdcolor.is_empty() {
+            let r = self.bkgdcolor[0];
+            let g = self.bkgdcolor[1];
+            let b = self.bkgdcolor[2];
+            block.set_background((r,g,b))
+        } else {
+            block
+        };
+
+        /* Limit colors to the output capability */
+        let color_depth = self.io.color_depth();
+        let block = block.color_depth(color_depth);
+        let block = block.metric(self.io.metric());
+        let block = block.theme(self.io.theme());
+        let block =
+        if let Some(quantizer) = self.mapping.quantizer(color_depth.palette().is_some()) {
+            block.quantize(quantizer)
+        } else {
+            block
+        };
+        let block =
+        if let Some(dithering) = self.mapping.dithering() {
+            block.dither(dithering)
+        } else {
+            block
+        };
+
         /* Set size */
         let block = 
         if self.fullscreen {
@@ -64,34 +98,30 @@ impl Block {
         let block = block.contrast(self.contrast);
         let block = block.brighten(self.brightness);
 
+        /* Manual threshold of the monochrome modes, Otsu's method otherwise */
+        let block =
+        if let Some(threshold) = self.threshold {
+            block.threshold(threshold)
+        } else {
+            block
+        };
+
         /* Convertion Method */        
         let block = 
-        match &self.block_mode[..] {
+        match self.block_mode.to_uppercase().as_str() {
             "HALF" => block.half(),
             "WHOLE" =>  block.whole(),
+            "QUADRANT" => block.quadrant(),
+            "QUADRANT-MONO" => block.quadrant_monochrome(),
+            /* Fall back to quadrants when the font lacks octants */
+            "OCTANT" if self.no_octants => block.quadrant(),
+            "OCTANT-MONO" if self.no_octants => block.quadrant_monochrome(),
+            "OCTANT" => block.octant(),
+            "OCTANT-MONO" => block.octant_monochrome(),
             _ =>  block.half(),
         };
 
 
-        /* Convert image to block */
-        //let ansi_output = block.convert(&self.image).unwrap();
-        let ansi_output = match block.convert(&self.image) {
-            Ok(a) => a,
-            Err(e) => return Err(e),
-        };
-
-        /* Print to stdout */
-        if !self.noecho {
-            ansi_output.print();
-        }
-
-        /*Save to output file*/
-        if !self.output.is_empty() {
-            if let Err(e) = ansi_output.save(&self.output[0]) {
-                return Err(e);
-            }
-        }
-
-        Ok(())
+        Box::new(block)
     }
 }
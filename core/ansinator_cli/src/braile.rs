@@ -2,21 +2,20 @@
 //!
 //! Functions for image ascii convertion with the following features:
 //!
+//! + Manual threshold, automatic threshold, dithering or adaptive threshold binarization
 //! + Best fitting braile 8-dot character analysis 
 //! + RGB coloring (fixed foreground and fixed background)
+//! + RGB and 256 terminal coloring (mean of the set dots, optionally mean of the unset dots as background),
+//!   selected by the color capability of the output when no color is given
 //! + Bold, Blink ansi styles
 
 use crate::args::Braile;
-use ansinator_ansi_image::{braile::AnsiBraile, ansi::Ansinator};
-use ansinator_ansi_image::error::AnsiImageError;
-
-//use std::error::Error;
-
-//type MyResult<T> = Result<T, Box<dyn Error>>;
-type MyResult<T> = Result<T, AnsiImageError>;
+use ansinator_ansi_image::{braile::AnsiBraile, ansi::{Ansinator, Renderer}};
+use ansinator_terminal_colors::ColorDepth;
 
 impl Braile {
-    pub fn run(&self) -> MyResult<()> {
+    /// Build the configured renderer
+    pub fn renderer(&self) -> Box<dyn Renderer> {
         let braile = AnsiBraile::new();
         /* Ansi style */
         
@@ -53,6 +52,34 @@ impl Braile {
             braile
         };
 
+        /* Color the dots as supported when no color mode is given */
+        let color_depth = self.io.color_depth();
+        let braile =
+        if self.rgbcolor {
+            braile.true_color()
+        } else if self.termcolor {
+            braile.terminal_color()
+        } else if !self.frgdcolor.is_empty() || !self.bkgdcolor.is_empty() {
+            braile
+        } else {
+            match color_depth {
+                ColorDepth::Truecolor => braile.true_color(),
+                ColorDepth::Ansi256 | ColorDepth::Ansi16 | ColorDepth::Ansi8 => braile.terminal_color(),
+                ColorDepth::None => braile,
+            }
+        };
+        let braile =
+        if self.mean_background {
+            braile.mean_background()
+        } else {
+            braile
+        };
+
+        /* Limit colors to the output capability */
+        let braile = braile.color_depth(color_depth);
+        let braile = braile.metric(self.io.metric());
+        let braile = braile.theme(self.io.theme());
+
         /* Set size */
         let braile = 
         if self.fullscreen {
@@ -73,33 +100,18 @@ impl Braile {
         let braile = braile.contrast(self.contrast);
         let braile = braile.brighten(self.brightness);
 
-        /* Binarize Method manual threshold or automatic otsu's method */
+        /* Binarize Method manual threshold, dithering, adaptive threshold or automatic global method */
         let braile = 
-        if !self.threshold.is_empty() {
-            braile.threshold(self.threshold[0])
+        if let Some(threshold) = self.binarize.threshold() {
+            braile.threshold(threshold)
+        } else if let Some(dithering) = self.binarize.dithering() {
+            braile.dither(dithering)
+        } else if let Some(adaptive) = self.binarize.adaptive_threshold() {
+            braile.adaptive_threshold(adaptive)
         } else {
-            braile.otsu_threshold()
+            braile.auto_threshold(self.binarize.threshold_method())
         };
 
-        /* Convert image to braile */
-        //let ansi_output = braile.convert(&self.image).unwrap();
-        let ansi_output = match braile.convert(&self.image) {
-            Ok(a) => a,
-            Err(e) => return Err(e),
-        };
-
-        /* Print to stdout */
-        if !self.noecho {
-            ansi_output.print();
-        }
-
-        /*Save to output file*/
-        if !self.output.is_empty() {
-            if let Err(e) = ansi_output.save(&self.output[0]) {
-                return Err(e);
-            }
-        }
-
-        Ok(())
+        Box::new(braile)
     }
 }
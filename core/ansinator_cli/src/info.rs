@@ -0,0 +1,101 @@
+//! Image information
+//!
+//! Read-only analysis of an image with the following features:
+//!
+//! + Luma and per channel minimum, maximum, mean, standard deviation and percentiles
+//! + Threshold suggestions of every global method and multi-level Otsu's method
+//! + Luma histogram rendered as braile 8-dot characters
+
+use crate::args::Info;
+use crate::binarize::threshold_method_name;
+use ansinator_ansi_image::{ansi::open_image, braile::window_analysis, error::AnsiImageError};
+use ansinator_image_binarize::{Analysis, ChannelStatistics};
+use image::{GrayImage, Luma};
+
+impl Info {
+    /// Print the statistics, thresholds and histogram of the image
+    pub fn run(&self) -> Result<(), AnsiImageError> {
+        let image = open_image(&self.image)?.to_rgb8();
+        let luma = image.luma_statistics();
+        let channels = image.channel_statistics();
+
+        println!("{}: {}x{}, {} pixels", self.image, image.width(), image.height(), luma.count());
+
+        /* Statistics */
+        println!();
+        println!("{:<8}{:>5}{:>5}{:>9}{:>9}{:>5}{:>5}{:>8}{:>5}{:>5}",
+                 "channel", "min", "max", "mean", "std", "p5", "p25", "median", "p75", "p95");
+        for (name, statistics) in ["luma", "red", "green", "blue"].iter().zip([&luma].into_iter().chain(channels.iter())) {
+            print_statistics(name, statistics);
+        }
+
+        /* Threshold suggestions */
+        println!();
+        println!("thresholds");
+        for (method, value) in luma.thresholds() {
+            println!("  {:<12}{:>4}", threshold_method_name(method), value);
+        }
+        let levels: Vec<String> = luma.multi_otsu_thresholds(self.levels as usize)
+            .iter()
+            .map(|value| value.to_string())
+            .collect();
+        println!("  {:<12}{:>4}", "multi-otsu", levels.join(" "));
+
+        /* Histogram */
+        println!();
+        println!("luma histogram");
+        for line in histogram_lines(&luma, self.width, self.height) {
+            println!("{}", line);
+        }
+        println!("0{:>width$}", 255, width = self.width as usize - 1);
+
+        Ok(())
+    }
+}
+
+/// Print a row of the statistics table
+fn print_statistics(name: &str, statistics: &ChannelStatistics) {
+    println!("{:<8}{:>5}{:>5}{:>9.2}{:>9.2}{:>5}{:>5}{:>8}{:>5}{:>5}",
+             name,
+             statistics.min().unwrap_or(0),
+             statistics.max().unwrap_or(0),
+             statistics.mean(),
+             statistics.std_dev(),
+             statistics.percentile(5.0),
+             statistics.percentile(25.0),
+             statistics.median(),
+             statistics.percentile(75.0),
+             statistics.percentile(95.0));
+}
+
+/// Render the histogram as `height` lines of `width` braile characters
+///
+/// Each character holds two bars of 4 dots, so the 256 values are grouped
+/// in `2 * width` bins scaled to the highest one.
+fn histogram_lines(statistics: &ChannelStatistics, width: u32, height: u32) -> Vec<String> {
+    let bins = 2 * width as usize;
+    let dots = 4 * height;
+
+    let mut counts = vec![0usize; bins];
+    for (value, &count) in statistics.histogram().iter().enumerate() {
+        counts[value * bins / 256] += count;
+    }
+    let highest = counts.iter().copied().max().unwrap_or(0).max(1);
+
+    /* Bars grow from the bottom, non empty bins show at least a dot */
+    let bars: Vec<u32> = counts.iter()
+        .map(|&count| {
+            let bar = (count as f64 / highest as f64 * dots as f64).round() as u32;
+            if count > 0 { bar.max(1) } else { 0 }
+        })
+        .collect();
+    let plot = GrayImage::from_fn(bins as u32, dots, |x, y| {
+        if dots - y <= bars[x as usize] { Luma([255]) } else { Luma([0]) }
+    });
+
+    (0..height).map(|row| {
+        (0..width).map(|column| window_analysis(&plot, 2 * column, 4 * row))
+            .collect()
+    })
+    .collect()
+}
@@ -5,33 +5,67 @@ mod ascii;
 mod braile;
 mod block;
 mod uniblock;
+mod output;
+mod binarize;
+mod info;
 
 use clap::Parser;
-use args::AnsinatorArgs;
+use args::{AnsinatorArgs, IoArgs};
+use ansinator_ansi_image::{ansi::Renderer, error::AnsiImageError};
 use std::process;
 
 fn main() {
     let args = AnsinatorArgs::parse();
     //println!("{:?}", args);
 
-    if let Err(e) = 
+    let (renderer, io) =
         match &args.command {
             args::AnsinatorCommands::Ascii(ascii) => {
-                ascii.run()
+                (ascii.renderer(), &ascii.io)
             },
             args::AnsinatorCommands::Braile(braile) => {
-                braile.run()
+                (braile.renderer(), &braile.io)
             },
             args::AnsinatorCommands::Block(block) => {
-                block.run() 
+                (block.renderer(), &block.io)
             },
             args::AnsinatorCommands::Uniblock(uniblock) => {
-                uniblock.run() 
+                (uniblock.renderer(), &uniblock.io)
             },
-        }
-    {
+            args::AnsinatorCommands::Info(info) => {
+                if let Err(e) = info.run() {
+                    eprintln!("{:?}",e);
+                    process::exit(1);
+                }
+                return;
+            },
+        };
+
+    if let Err(e) = run(renderer.as_ref(), io) {
         eprintln!("{:?}",e);
         process::exit(1);
     }
 
 }
+
+/// Convert the input image with the given renderer, then print and save the result
+fn run(renderer: &dyn Renderer, io: &IoArgs) -> Result<(), AnsiImageError> {
+    /* Convert image */
+    let ansi_output = renderer.render_path(&io.image)?;
+
+    /* Print to stdout, a closed pipe (like `| head`) just stops printing */
+    if !io.noecho {
+        match ansi_output.print_as(&io.stdout_format()) {
+            Err(AnsiImageError::WriteError(e)) if e.kind() == std::io::ErrorKind::BrokenPipe => {},
+            result => result?,
+        }
+    }
+
+    /*Save to output file*/
+    if !io.output.is_empty() {
+        let path = &io.output[0];
+        ansi_output.save_as(path, &io.output_format(path))?;
+    }
+
+    Ok(())
+}
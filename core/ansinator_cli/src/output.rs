@@ -0,0 +1,151 @@
+//! Output color and format selection
+//!
+//! Select the color capability of the output, the metric, theme, adaptive
+//! palette and dithering approximating colors, the format of the printed convertion by the environment and of
+//! the saved convertion by flag or by file extension, along the options of
+//! each format.
+
+use crate::args::{ColorMappingArgs, IoArgs};
+use crate::binarize::dithering_method;
+use ansinator_ansi_image::{ansi::OutputFormat, html::HtmlOptions, svg::SvgOptions, raster::RasterOptions};
+use ansinator_image_binarize::Dithering;
+use ansinator_terminal_colors::{self as terminal_colors, ColorDepth, DistanceMetric, QuantizeMethod, Quantizer, Theme, ThemeError};
+use std::io::{self, IsTerminal};
+
+impl IoArgs {
+    /// Get the color capability of the output, detected from the environment on auto
+    pub fn color_depth(&self) -> ColorDepth {
+        match self.color.to_lowercase().as_str() {
+            "truecolor" => ColorDepth::Truecolor,
+            "256" => ColorDepth::Ansi256,
+            "16" => ColorDepth::Ansi16,
+            "8" => ColorDepth::Ansi8,
+            "none" => ColorDepth::None,
+            _ => terminal_colors::detect_color_depth(),
+        }
+    }
+
+    /// Get the metric used to approximate colors on the terminal palettes
+    pub fn metric(&self) -> DistanceMetric {
+        match self.metric.to_lowercase().as_str() {
+            "redmean" => DistanceMetric::Redmean,
+            "cie76" => DistanceMetric::Cie76,
+            "ciede2000" => DistanceMetric::Ciede2000,
+            _ => DistanceMetric::Rgb,
+        }
+    }
+
+    /// Get the real colors of the terminal, the xterm ones unless a palette is given
+    pub fn theme(&self) -> Theme {
+        self.palette.clone().unwrap_or_default()
+    }
+
+    /// Get the format printed to stdout, plain text if asked, if `NO_COLOR` is set
+    /// or if stdout is not a terminal unless colors are forced or given
+    pub fn stdout_format(&self) -> OutputFormat {
+        let forced = terminal_colors::clicolor_force() || !self.color.eq_ignore_ascii_case("auto");
+
+        if self.plain || terminal_colors::no_color() || (!forced && !io::stdout().is_terminal()) {
+            self.plain_format()
+        } else {
+            OutputFormat::Ansi
+        }
+    }
+
+    fn plain_format(&self) -> OutputFormat {
+        OutputFormat::Plain { trim_trailing: self.strip_trailing }
+    }
+
+    /// Get the format of the output file in `path`
+    pub fn output_format(&self, path: &str) -> OutputFormat {
+        let format =
+        match self.format.as_deref().map(|f| f.to_uppercase()).as_deref() {
+            Some("ANSI") => OutputFormat::Ansi,
+            Some("PLAIN") => self.plain_format(),
+            Some("HTML") => OutputFormat::Html(HtmlOptions::new()),
+            Some("SVG") => OutputFormat::Svg(SvgOptions::new()),
+            Some("PNG") => OutputFormat::Png(RasterOptions::new()),
+            _ => {
+                match OutputFormat::from_path(path) {
+                    OutputFormat::Ansi if self.plain => self.plain_format(),
+                    format => format,
+                }
+            },
+        };
+
+        match format {
+            OutputFormat::Html(_) => {
+                let options = HtmlOptions::new().theme(&self.theme());
+                let options =
+                if self.html_fragment {
+                    options.fragment()
+                } else {
+                    options.standalone()
+                };
+                let options =
+                if self.html_classes {
+                    options.classes()
+                } else {
+                    options.inline()
+                };
+
+                OutputFormat::Html(options)
+            },
+            OutputFormat::Svg(_) => {
+                let options = SvgOptions::new().theme(&self.theme());
+                let options =
+                if !self.svg_cell_size.is_empty() {
+                    options.cell_size(self.svg_cell_size[0], self.svg_cell_size[1])
+                } else {
+                    options
+                };
+                let options =
+                match &self.svg_font {
+                    Some(font) => options.font_family(font),
+                    None => options,
+                };
+                let options =
+                if self.svg_exact_blocks {
+                    options.exact_blocks()
+                } else {
+                    options
+                };
+
+                OutputFormat::Svg(options)
+            },
+            OutputFormat::Png(_) => {
+                OutputFormat::Png(RasterOptions::new().scale(self.png_scale).theme(&self.theme()))
+            },
+            _ => format,
+        }
+    }
+}
+
+impl ColorMappingArgs {
+    /// Get the quantizer of the adaptive palette, `None` if not quantizing,
+    /// restricted to terminal colors if asked or if `terminal`
+    pub fn quantizer(&self, terminal: bool) -> Option<Quantizer> {
+        let quantizer = Quantizer::new(self.quantize? as usize);
+        let quantizer =
+        match self.quantize_method.to_lowercase().as_str() {
+            "kmeans" => quantizer.method(QuantizeMethod::KMeans),
+            _ => quantizer.method(QuantizeMethod::MedianCut),
+        };
+
+        if self.quantize_terminal || terminal {
+            Some(quantizer.terminal())
+        } else {
+            Some(quantizer)
+        }
+    }
+
+    /// Get the dithering of the approximated colors, `None` if not dithering
+    pub fn dithering(&self) -> Option<Dithering> {
+        self.dither.as_deref().map(dithering_method)
+    }
+}
+
+/// Load the theme of the `--palette` file
+pub fn parse_palette(path: &str) -> Result<Theme, ThemeError> {
+    Theme::from_file(path)
+}
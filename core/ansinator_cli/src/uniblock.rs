@@ -2,20 +2,21 @@
 //!
 //! Functions for image uniblock (sextant) convertion with the following features:
 //!
+//! + Manual threshold, automatic threshold, dithering or adaptive threshold binarization
+//! + Two color fitting of the sextant, quadrant or octant glyphs
 //! + Best fitting character analysis 
 //! + RGB coloring (fixed foreground and fixed background)
+//! + RGB and 256 terminal coloring (mean of the set and unset pixels),
+//!   selected by the color capability of the output when no color is given
 //! + Bold and Blink ansi styles
 
 use crate::args::Uniblock;
-use ansinator_ansi_image::{uniblock::AnsiUniblock, ansi::Ansinator, error::AnsiImageError};
-
-//use std::error::Error;
-
-//type MyResult<T> = Result<T, Box<dyn Error>>;
-type MyResult<T> = Result<T, AnsiImageError>;
+use ansinator_ansi_image::{uniblock::{AnsiUniblock, FitGlyphs}, ansi::{Ansinator, Renderer}};
+use ansinator_terminal_colors::ColorDepth;
 
 impl Uniblock {
-    pub fn run(&self) -> MyResult<()> {
+    /// Build the configured renderer
+    pub fn renderer(&self) -> Box<dyn Renderer> {
         let uniblock = AnsiUniblock::new();
         /* Ansi style */
         
@@ -52,6 +53,28 @@ impl Uniblock {
             uniblock
         };
 
+        /* Color the pixels as supported when no color mode is given */
+        let color_depth = self.io.color_depth();
+        let uniblock =
+        if self.rgbcolor {
+            uniblock.true_color()
+        } else if self.termcolor {
+            uniblock.terminal_color()
+        } else if !self.frgdcolor.is_empty() || !self.bkgdcolor.is_empty() {
+            uniblock
+        } else {
+            match color_depth {
+                ColorDepth::Truecolor => uniblock.true_color(),
+                ColorDepth::Ansi256 | ColorDepth::Ansi16 | ColorDepth::Ansi8 => uniblock.terminal_color(),
+                ColorDepth::None => uniblock,
+            }
+        };
+
+        /* Limit colors to the output capability */
+        let uniblock = uniblock.color_depth(color_depth);
+        let uniblock = uniblock.metric(self.io.metric());
+        let uniblock = uniblock.theme(self.io.theme());
+
         /* Set size */
         let uniblock = 
         if self.fullscreen {
@@ -72,33 +95,30 @@ impl Uniblock {
         let uniblock = uniblock.contrast(self.contrast);
         let uniblock = uniblock.brighten(self.brightness);
 
-        /* Binarize Method manual threshold or automatic otsu's method */
+        /* Binarize Method manual threshold, dithering, adaptive threshold or automatic global method,
+         * unless fitting the colors */
         let uniblock = 
-        if !self.threshold.is_empty() {
-            uniblock.threshold(self.threshold[0])
+        if let Some(glyphs) = self.fit_glyphs() {
+            uniblock.color_fit(glyphs)
+        } else if let Some(threshold) = self.binarize.threshold() {
+            uniblock.threshold(threshold)
+        } else if let Some(dithering) = self.binarize.dithering() {
+            uniblock.dither(dithering)
+        } else if let Some(adaptive) = self.binarize.adaptive_threshold() {
+            uniblock.adaptive_threshold(adaptive)
         } else {
-            uniblock.otsu_threshold()
-        };
-
-        /* Convert image to uniblock */
-       // let ansi_output = uniblock.convert(&self.image);
-        let ansi_output = match uniblock.convert(&self.image) {
-            Ok(a) => a,
-            Err(e) => return Err(e),
+            uniblock.auto_threshold(self.binarize.threshold_method())
         };
 
-        /* Print to stdout */
-        if !self.noecho {
-            ansi_output.print();
-        }
+        Box::new(uniblock)
+    }
 
-        /*Save to output file*/
-        if !self.output.is_empty() {
-            if let Err(e) = ansi_output.save(&self.output[0]) {
-                return Err(e);
-            }
+    /// Get the glyphs of the two color fitting, `None` if not fitting
+    fn fit_glyphs(&self) -> Option<FitGlyphs> {
+        match self.fit.as_ref()?.to_lowercase().as_str() {
+            "quadrant" => Some(FitGlyphs::Quadrant),
+            "octant" => Some(FitGlyphs::Octant),
+            _ => Some(FitGlyphs::Sextant),
         }
-
-        Ok(())
     }
 }
@@ -0,0 +1,198 @@
+//! Read-only image analysis
+//!
+//! Statistics of the luma and of each channel of an image, to inspect it
+//! before choosing the convertion settings:
+//! + Histogram
+//! + Minimum, maximum, mean, variance and standard deviation
+//! + Percentiles
+//! + Threshold suggestions of the global methods
+
+use crate::histogram::{self, Histogram};
+use crate::ThresholdMethod;
+use image::{GrayImage, Pixel, RgbImage};
+
+/// Statistics of the values of a channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelStatistics {
+    histogram: Histogram,
+}
+
+impl ChannelStatistics {
+    /// Create the statistics of a histogram
+    pub fn from_histogram(histogram: Histogram) -> Self {
+        Self { histogram }
+    }
+
+    /// Create the statistics of the values
+    pub fn from_values<I: IntoIterator<Item = u8>>(values: I) -> Self {
+        let mut histogram = [0; 256];
+        values.into_iter().for_each(|value| histogram[value as usize] += 1);
+        Self { histogram }
+    }
+
+    /// Get the histogram of the values
+    pub fn histogram(&self) -> &Histogram {
+        &self.histogram
+    }
+
+    /// Get the number of values
+    pub fn count(&self) -> usize {
+        self.histogram.iter().sum()
+    }
+
+    /// Get the lowest value, `None` without values
+    pub fn min(&self) -> Option<u8> {
+        self.histogram.iter().position(|&count| count > 0).map(|value| value as u8)
+    }
+
+    /// Get the highest value, `None` without values
+    pub fn max(&self) -> Option<u8> {
+        self.histogram.iter().rposition(|&count| count > 0).map(|value| value as u8)
+    }
+
+    /// Get the mean of the values, zero without values
+    pub fn mean(&self) -> f64 {
+        let count = self.count();
+        if count == 0 {
+            return 0.0;
+        }
+        let sum: f64 = self.histogram.iter().enumerate().map(|(value, &n)| (value * n) as f64).sum();
+        sum / count as f64
+    }
+
+    /// Get the population variance of the values, zero without values
+    pub fn variance(&self) -> f64 {
+        let count = self.count();
+        if count == 0 {
+            return 0.0;
+        }
+        let mean = self.mean();
+        let sum: f64 = self.histogram.iter().enumerate().map(|(value, &n)| n as f64 * (value as f64 - mean).powi(2)).sum();
+        sum / count as f64
+    }
+
+    /// Get the standard deviation of the values
+    pub fn std_dev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+
+    /// Get the lowest value with at least `p` percent of the values lower or equal, zero without values
+    pub fn percentile(&self, p: f64) -> u8 {
+        let count = self.count();
+        if count == 0 {
+            return 0;
+        }
+        /* Nearest rank */
+        let rank = ((p.clamp(0.0, 100.0) / 100.0 * count as f64).ceil() as usize).max(1);
+
+        let mut accumulated = 0;
+        for (value, &n) in self.histogram.iter().enumerate() {
+            accumulated += n;
+            if accumulated >= rank {
+                return value as u8;
+            }
+        }
+        255
+    }
+
+    /// Get the median of the values
+    pub fn median(&self) -> u8 {
+        self.percentile(50.0)
+    }
+
+    /// Get the threshold value suggested by a global method
+    pub fn threshold(&self, method: ThresholdMethod) -> u8 {
+        histogram::threshold_value(&self.histogram, method)
+    }
+
+    /// Get the threshold values suggested by every global method
+    pub fn thresholds(&self) -> Vec<(ThresholdMethod, u8)> {
+        ThresholdMethod::ALL.iter()
+            .map(|&method| (method, self.threshold(method)))
+            .collect()
+    }
+
+    /// Get `k` increasing thresholds of multi-level Otsu's method
+    ///
+    /// `k` is clamped to `1..=255`, so `0` gives a single threshold as `1` does.
+    pub fn multi_otsu_thresholds(&self, k: usize) -> Vec<u8> {
+        histogram::multi_otsu_values(&self.histogram, k)
+    }
+}
+
+/// Read-only analysis of the values of an image
+pub trait Analysis {
+    /// Get the statistics of the luma
+    fn luma_statistics(&self) -> ChannelStatistics;
+    /// Get the statistics of each channel, red, green and blue for color images
+    fn channel_statistics(&self) -> Vec<ChannelStatistics>;
+}
+
+impl Analysis for GrayImage {
+    fn luma_statistics(&self) -> ChannelStatistics {
+        ChannelStatistics::from_values(self.iter().copied())
+    }
+
+    fn channel_statistics(&self) -> Vec<ChannelStatistics> {
+        vec![self.luma_statistics()]
+    }
+}
+
+impl Analysis for RgbImage {
+    fn luma_statistics(&self) -> ChannelStatistics {
+        ChannelStatistics::from_values(self.pixels().map(|p| p.to_luma()[0]))
+    }
+
+    fn channel_statistics(&self) -> Vec<ChannelStatistics> {
+        (0..3).map(|c| ChannelStatistics::from_values(self.pixels().map(|p| p[c])))
+            .collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Luma, Rgb};
+
+    #[test]
+    fn channel_statistics_values() {
+        let statistics = ChannelStatistics::from_values([10, 20, 20, 30, 40, 50, 60, 70, 80, 90]);
+
+        assert_eq!(statistics.count(), 10);
+        assert_eq!((statistics.min(), statistics.max()), (Some(10), Some(90)));
+        assert!((statistics.mean() - 47.0).abs() < 1e-9);
+        assert!((statistics.variance() - 681.0).abs() < 1e-9);
+        assert_eq!(statistics.median(), 40);
+        assert_eq!(statistics.percentile(0.0), 10);
+        assert_eq!(statistics.percentile(25.0), 20);
+        assert_eq!(statistics.percentile(90.0), 80);
+        assert_eq!(statistics.percentile(100.0), 90);
+
+        let empty = ChannelStatistics::from_histogram([0; 256]);
+        assert_eq!((empty.min(), empty.mean(), empty.median()), (None, 0.0, 0));
+    }
+
+    #[test]
+    fn image_analysis_is_read_only() {
+        let gray = GrayImage::from_fn(8, 8, |x, _| Luma([if x < 4 { 30 } else { 220 }]));
+        let copy = gray.clone();
+
+        let statistics = gray.luma_statistics();
+        assert_eq!(gray, copy);
+        assert_eq!(gray.channel_statistics(), vec![statistics.clone()]);
+
+        /* Every suggestion splits the two values */
+        for (method, threshold) in statistics.thresholds() {
+            assert!((30..=220).contains(&threshold), "{:?} {}", method, threshold);
+        }
+
+        let rgb = RgbImage::from_fn(4, 4, |x, _| Rgb([255, 10 * x as u8, 0]));
+        let channels = rgb.channel_statistics();
+        assert_eq!(channels.len(), 3);
+        assert_eq!(channels[0].min(), Some(255));
+        assert!((channels[1].mean() - 15.0).abs() < 1e-9);
+        assert_eq!(channels[2].max(), Some(0));
+        assert_eq!(rgb.luma_statistics().count(), 16);
+    }
+}
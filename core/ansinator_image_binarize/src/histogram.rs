@@ -0,0 +1,402 @@
+//! Global thresholds of a histogram
+//!
+//! Computes the threshold value of a 256 bins histogram by:
+//! + Otsu's method
+//! + Triangle method
+//! + Kapur's maximum entropy
+//! + Yen's maximum correlation
+//! + Isodata (Ridler-Calvard) iterative selection
+//! + Li's minimum cross entropy
+//! + Multi-level Otsu's method
+//!
+//! Every threshold `t` splits the values in `0..=t` and `t+1..=255`,
+//! but Otsu's, which is the first value of the upper class.
+
+use crate::ThresholdMethod;
+
+/// Histogram of the pixel values
+pub type Histogram = [usize; 256];
+
+/// Get the first and last values with pixels, `None` if the histogram is empty
+fn value_range(histogram: &Histogram) -> Option<(usize, usize)> {
+    let first = histogram.iter().position(|&count| count > 0)?;
+    let last = histogram.iter().rposition(|&count| count > 0)?;
+    Some((first, last))
+}
+
+/// Get the mean value of the pixels of `low..=high`, `None` without pixels
+fn mean(histogram: &Histogram, low: usize, high: usize) -> Option<f64> {
+    let (count, sum) = (low..=high).fold((0.0, 0.0), |(count, sum), i| {
+        (count + histogram[i] as f64, sum + (i * histogram[i]) as f64)
+    });
+    if count > 0.0 { Some(sum / count) } else { None }
+}
+
+/// Get the threshold value of a global method
+pub fn threshold_value(histogram: &Histogram, method: ThresholdMethod) -> u8 {
+    match method {
+        ThresholdMethod::Otsu => otsu_value(histogram),
+        ThresholdMethod::Triangle => triangle_value(histogram),
+        ThresholdMethod::Kapur => kapur_value(histogram),
+        ThresholdMethod::Yen => yen_value(histogram),
+        ThresholdMethod::Isodata => isodata_value(histogram),
+        ThresholdMethod::Li => li_value(histogram),
+    }
+}
+
+/// Otsu's method, maximizing the variance between the two classes
+///
+/// See [`crate::Threshold::otsu_threshold`].
+pub fn otsu_value(histogram: &Histogram) -> u8 {
+    let total_weight = histogram.iter().sum::<usize>() as f64;
+    let mut bg_sum = 0.0;
+    let mut bg_weight = 0.0;
+    let mut max_variance = 0.0;
+    let mut best_threshold = 0;
+    let sum_intensity: f64 = histogram
+        .iter()
+        .enumerate()
+        .fold(0f64, |acu, (t, c)| acu + (t * c) as f64);
+
+    for (threshold, count) in histogram.iter().enumerate() {
+        let fg_weight = total_weight - bg_weight;
+        if fg_weight > 0.0 && bg_weight > 0.0 {
+            let fg_mean = (sum_intensity - bg_sum) / fg_weight;
+            let val = (bg_weight * fg_weight * ((bg_sum / bg_weight) - fg_mean)).powi(2);
+            if val >= max_variance {
+                best_threshold = threshold as u8;
+                max_variance = val;
+            }
+        }
+        bg_weight += *count as f64;
+        bg_sum += (threshold * count) as f64;
+    }
+
+    best_threshold
+}
+
+/// Triangle method, by Zack, Rogers and Latt
+///
+/// Draws a line from the peak of the histogram to the end of its longest tail,
+/// the threshold is the value farthest from the line. Suited to histograms
+/// with a single peak, as line art on a plain background.
+pub fn triangle_value(histogram: &Histogram) -> u8 {
+    let (first, last) = match value_range(histogram) {
+        Some(range) => range,
+        None => return 0,
+    };
+    let peak = (first..=last).fold(first, |peak, i| if histogram[i] > histogram[peak] { i } else { peak });
+
+    /* Search on the longest tail */
+    let end = if peak - first > last - peak { first } else { last };
+    let (low, high) = if end < peak { (end, peak) } else { (peak, end) };
+
+    /* Distance to the line from (end, h[end]) to (peak, h[peak]), up to a constant factor */
+    let (x0, y0) = (end as f64, histogram[end] as f64);
+    let (x1, y1) = (peak as f64, histogram[peak] as f64);
+    let distance = |i: usize| ((y1 - y0) * i as f64 - (x1 - x0) * histogram[i] as f64 + x1 * y0 - y1 * x0).abs();
+
+    let mut threshold = low;
+    let mut max = -1.0;
+    for i in low..=high {
+        let d = distance(i);
+        if d > max {
+            max = d;
+            threshold = i;
+        }
+    }
+
+    /* The farthest value belongs to the tail */
+    if end < peak {
+        threshold as u8
+    } else {
+        threshold.saturating_sub(1) as u8
+    }
+}
+
+/// Kapur, Sahoo and Wong maximum entropy method
+///
+/// Maximizes the sum of the entropies of the two classes.
+pub fn kapur_value(histogram: &Histogram) -> u8 {
+    let total: f64 = histogram.iter().sum::<usize>() as f64;
+    if total == 0.0 {
+        return 0;
+    }
+    let p: Vec<f64> = histogram.iter().map(|&count| count as f64 / total).collect();
+
+    /* Entropy of the values of a class of probability `weight` */
+    let entropy = |values: &[f64], weight: f64| -> f64 {
+        values.iter()
+            .filter(|&&pi| pi > 0.0)
+            .map(|&pi| -(pi / weight) * (pi / weight).ln())
+            .sum()
+    };
+
+    let mut threshold = 0;
+    let mut max = f64::NEG_INFINITY;
+    let mut weight = 0.0;
+    for t in 0..255 {
+        weight += p[t];
+        if weight <= 0.0 || weight >= 1.0 {
+            continue;
+        }
+        let h = entropy(&p[..=t], weight) + entropy(&p[t + 1..], 1.0 - weight);
+        if h > max {
+            max = h;
+            threshold = t;
+        }
+    }
+
+    threshold as u8
+}
+
+/// Yen, Chang and Chang maximum correlation method
+///
+/// Maximizes the correlation of the two classes, a cheaper entropy criterion.
+pub fn yen_value(histogram: &Histogram) -> u8 {
+    let total: f64 = histogram.iter().sum::<usize>() as f64;
+    if total == 0.0 {
+        return 0;
+    }
+    let p: Vec<f64> = histogram.iter().map(|&count| count as f64 / total).collect();
+    let squares_total: f64 = p.iter().map(|pi| pi * pi).sum();
+
+    let mut threshold = 0;
+    let mut max = f64::NEG_INFINITY;
+    let (mut weight, mut squares) = (0.0, 0.0);
+    for (t, &pt) in p.iter().enumerate().take(255) {
+        weight += pt;
+        squares += pt * pt;
+        let above = squares_total - squares;
+        if weight <= 0.0 || weight >= 1.0 || squares <= 0.0 || above <= 0.0 {
+            continue;
+        }
+        let criterion = -(squares * above).ln() + 2.0 * (weight * (1.0 - weight)).ln();
+        if criterion > max {
+            max = criterion;
+            threshold = t;
+        }
+    }
+
+    threshold as u8
+}
+
+/// Isodata method, by Ridler and Calvard
+///
+/// Iterates the threshold as the middle of the means of the two classes.
+pub fn isodata_value(histogram: &Histogram) -> u8 {
+    let (first, last) = match value_range(histogram) {
+        Some(range) => range,
+        None => return 0,
+    };
+    let mut threshold = mean(histogram, first, last).unwrap_or(0.0) as usize;
+
+    for _ in 0..256 {
+        let below = mean(histogram, first, threshold);
+        let above = if threshold < last { mean(histogram, threshold + 1, last) } else { None };
+        let next = match (below, above) {
+            (Some(below), Some(above)) => ((below + above) / 2.0) as usize,
+            _ => break,
+        };
+        if next == threshold {
+            break;
+        }
+        threshold = next;
+    }
+
+    threshold as u8
+}
+
+/// Li and Tam iterative minimum cross entropy method
+///
+/// Minimizes the cross entropy between the image and its binarization.
+pub fn li_value(histogram: &Histogram) -> u8 {
+    let (first, last) = match value_range(histogram) {
+        Some(range) => range,
+        None => return 0,
+    };
+    if first == last {
+        return first as u8;
+    }
+
+    /* Values shifted so the lowest one is zero, as the logarithms need positive means */
+    let shifted_mean = |low: usize, high: usize| mean(histogram, low, high).map(|m| m - first as f64);
+    let tolerance = 0.5;
+    let mut current = f64::NEG_INFINITY;
+    let mut next = shifted_mean(first, last).unwrap_or(0.0);
+
+    while (next - current).abs() > tolerance {
+        current = next;
+        let split = first + current as usize;
+        let background = shifted_mean(first, split);
+        let foreground = shifted_mean((split + 1).min(last), last);
+        next = match (background, foreground) {
+            (Some(background), Some(foreground)) if background > 0.0 && foreground > background => {
+                (foreground - background) / (foreground.ln() - background.ln())
+            },
+            _ => break,
+        };
+    }
+
+    (first as f64 + current) as u8
+}
+
+/// Multi-level Otsu's method, returning `k` increasing thresholds splitting the values in `k + 1` classes
+///
+/// Maximizes the variance between the classes with dynamic programming,
+/// in `O(k 256²)` time. `k` is clamped to `1..=255`, so `0` gives a single threshold as `1` does.
+pub fn multi_otsu_values(histogram: &Histogram, k: usize) -> Vec<u8> {
+    let k = k.clamp(1, 255);
+    let classes = k + 1;
+
+    /* Cumulative pixel counts and sums, from 0 to i - 1 */
+    let mut counts = [0.0f64; 257];
+    let mut sums = [0.0f64; 257];
+    for i in 0..256 {
+        counts[i + 1] = counts[i] + histogram[i] as f64;
+        sums[i + 1] = sums[i] + (i * histogram[i]) as f64;
+    }
+    /* Between class variance term of the values low..=high */
+    let term = |low: usize, high: usize| -> f64 {
+        let count = counts[high + 1] - counts[low];
+        let sum = sums[high + 1] - sums[low];
+        if count > 0.0 { sum * sum / count } else { 0.0 }
+    };
+
+    /* best[c][i]: best split of 0..=i in c + 1 classes, ending the previous class at from[c][i] */
+    let mut best = vec![[f64::NEG_INFINITY; 256]; classes];
+    let mut from = vec![[0usize; 256]; classes];
+    for (i, value) in best[0].iter_mut().enumerate() {
+        *value = term(0, i);
+    }
+    for c in 1..classes {
+        for i in c..256 {
+            for j in c - 1..i {
+                let value = best[c - 1][j] + term(j + 1, i);
+                if value > best[c][i] {
+                    best[c][i] = value;
+                    from[c][i] = j;
+                }
+            }
+        }
+    }
+
+    /* Follow the class ends back from the last value */
+    let mut thresholds = vec![0u8; k];
+    let mut end = 255;
+    for c in (1..classes).rev() {
+        end = from[c][end];
+        thresholds[c - 1] = end as u8;
+    }
+
+    thresholds
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Histogram with a gaussian bump of `count` pixels at each `(center, spread)`
+    fn bimodal(peaks: &[(f64, f64, f64)]) -> Histogram {
+        let mut histogram = [0; 256];
+        for (i, bin) in histogram.iter_mut().enumerate() {
+            *bin = peaks.iter()
+                .map(|&(center, spread, count)| count * (-(i as f64 - center).powi(2) / (2.0 * spread * spread)).exp())
+                .sum::<f64>()
+                .round() as usize;
+        }
+        histogram
+    }
+
+    #[test]
+    fn triangle_skewed_histogram() {
+        /* Bright background peak with a long dark tail of line art */
+        let mut histogram = [0; 256];
+        for (i, bin) in histogram.iter_mut().enumerate().take(231).skip(20) {
+            *bin = 5;
+            if i > 200 {
+                *bin = 5 + (i - 200) * 100;
+            }
+        }
+        histogram[230] = 4000;
+
+        let t = triangle_value(&histogram);
+        assert!(t > 180 && t < 210, "{}", t);
+
+        /* Mirrored histogram, the tail on the bright side */
+        let mut mirrored = [0; 256];
+        for i in 0..256 {
+            mirrored[i] = histogram[255 - i];
+        }
+        let t_mirrored = triangle_value(&mirrored);
+        assert!(t_mirrored > 45 && t_mirrored < 75, "{}", t_mirrored);
+    }
+
+    #[test]
+    fn entropy_methods_split_modes() {
+        let histogram = bimodal(&[(50.0, 10.0, 1000.0), (190.0, 15.0, 600.0)]);
+
+        for (name, t) in [("kapur", kapur_value(&histogram)), ("yen", yen_value(&histogram))] {
+            assert!((65..160).contains(&t), "{} {}", name, t);
+        }
+    }
+
+    #[test]
+    fn isodata_two_values() {
+        let mut histogram = [0; 256];
+        histogram[40] = 100;
+        histogram[200] = 100;
+
+        /* Middle of the two means */
+        assert_eq!(isodata_value(&histogram), 120);
+
+        let histogram = bimodal(&[(60.0, 12.0, 800.0), (180.0, 12.0, 800.0)]);
+        let t = isodata_value(&histogram);
+        assert!(t > 110 && t < 130, "{}", t);
+    }
+
+    #[test]
+    fn li_between_modes() {
+        let histogram = bimodal(&[(60.0, 10.0, 800.0), (170.0, 20.0, 1200.0)]);
+        let t = li_value(&histogram);
+        assert!(t > 85 && t < 140, "{}", t);
+
+        let mut constant = [0; 256];
+        constant[77] = 10;
+        assert_eq!(li_value(&constant), 77);
+    }
+
+    #[test]
+    fn multi_otsu_three_modes() {
+        let histogram = bimodal(&[(30.0, 8.0, 500.0), (128.0, 8.0, 500.0), (220.0, 8.0, 500.0)]);
+        let thresholds = multi_otsu_values(&histogram, 2);
+
+        assert_eq!(thresholds.len(), 2);
+        assert!(thresholds[0] > 50 && thresholds[0] < 105, "{:?}", thresholds);
+        assert!(thresholds[1] > 150 && thresholds[1] < 200, "{:?}", thresholds);
+
+        /* A single threshold separates the same classes as Otsu's method */
+        let mut histogram = [0; 256];
+        for value in [120, 120, 21, 22, 25, 26, 27, 160, 180, 190, 123, 145, 165, 175, 23, 24] {
+            histogram[value] += 1;
+        }
+        let threshold = multi_otsu_values(&histogram, 1)[0];
+        assert!((27..120).contains(&threshold), "{}", threshold);
+
+        /* The number of thresholds is clamped */
+        assert_eq!(multi_otsu_values(&histogram, 0), vec![threshold]);
+        assert_eq!(multi_otsu_values(&histogram, 300).len(), 255);
+    }
+
+    #[test]
+    fn empty_histogram() {
+        let histogram = [0; 256];
+        assert_eq!(triangle_value(&histogram), 0);
+        assert_eq!(kapur_value(&histogram), 0);
+        assert_eq!(yen_value(&histogram), 0);
+        assert_eq!(isodata_value(&histogram), 0);
+        assert_eq!(li_value(&histogram), 0);
+        assert_eq!(multi_otsu_values(&histogram, 3).len(), 3);
+    }
+}
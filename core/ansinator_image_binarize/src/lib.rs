@@ -1,12 +1,111 @@
-//! GrayImage Threshold Trait. 
+//! GrayImage Threshold and Dither Traits. 
 //!
 //! Provide the Threshold Trait for GrayImage, implementing:
 //! + Otsu's binarization threshold 
 //! + Manual binarization threshold 
+//! + Triangle, Kapur, Yen, Isodata, Li and multi-level Otsu's global thresholds, see [`histogram`]
+//! + Mean-C, Gaussian-C, Niblack, Sauvola and Bradley-Roth local adaptive thresholds
 //! + Image pixel invertion
+//!
+//! Provide the Analysis Trait for GrayImage and RgbImage, with read-only
+//! statistics of the luma and of each channel, see [`analysis`]
+//!
+//! Provide the Dither Trait for GrayImage, implementing:
+//! + Floyd-Steinberg, Atkinson, Jarvis-Judice-Ninke and Sierra error diffusion
+//! + Bayer 2x2, 4x4 and 8x8 ordered dithering
+
+pub mod histogram;
+pub mod analysis;
+
+pub use analysis::{Analysis, ChannelStatistics};
 
 use image::GrayImage;
 
+/// Error diffusion kernel, as `(dx, dy, weight)` offsets from the current pixel
+pub type Kernel = &'static [(i32, i32, i32)];
+
+/// Automatic global threshold methods, analyzing the histogram of the image
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ThresholdMethod {
+    /// Otsu's method, maximizing the variance between the classes
+    #[default]
+    Otsu,
+    /// Triangle method, for a single peak with a long tail
+    Triangle,
+    /// Kapur's maximum entropy
+    Kapur,
+    /// Yen's maximum correlation
+    Yen,
+    /// Isodata (Ridler-Calvard) iterative selection
+    Isodata,
+    /// Li's minimum cross entropy
+    Li,
+}
+
+impl ThresholdMethod {
+    /// Every global method
+    pub const ALL: [ThresholdMethod; 6] = [
+        ThresholdMethod::Otsu,
+        ThresholdMethod::Triangle,
+        ThresholdMethod::Kapur,
+        ThresholdMethod::Yen,
+        ThresholdMethod::Isodata,
+        ThresholdMethod::Li,
+    ];
+}
+
+/// Local adaptive threshold methods, comparing each pixel with the
+/// statistics of the window around it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AdaptiveMethod {
+    /// Mean of the window minus `k`
+    MeanC,
+    /// Gaussian weighted mean of the window minus `k`
+    GaussianC,
+    /// Mean plus `k` times the standard deviation of the window
+    Niblack,
+    /// Mean scaled by `1 + k (s / 128 - 1)`, with `s` the standard deviation of the window
+    Sauvola,
+    /// Mean scaled by `1 - k`, Bradley and Roth method
+    Bradley,
+}
+
+/// Local adaptive threshold, with the size of the window and the `k` parameter of the method
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveThreshold {
+    method: AdaptiveMethod,
+    window: u32,
+    k: f64,
+}
+
+impl AdaptiveThreshold {
+    /// Create an adaptive threshold of a 15x15 window and the usual `k` of the method
+    pub fn new(method: AdaptiveMethod) -> Self {
+        let k = match method {
+            AdaptiveMethod::MeanC | AdaptiveMethod::GaussianC => 5.0,
+            AdaptiveMethod::Niblack => -0.2,
+            AdaptiveMethod::Sauvola => 0.34,
+            AdaptiveMethod::Bradley => 0.15,
+        };
+        Self { method, window: 15, k }
+    }
+
+    /// Set the size of the window around each pixel, at least 3 pixels
+    pub fn window(&self, window: u32) -> Self {
+        Self { window: window.max(3), .. *self }
+    }
+
+    /// Set the `k` parameter of the method
+    pub fn k(&self, k: f64) -> Self {
+        Self { k, .. *self }
+    }
+
+    /// Get the method
+    pub fn method(&self) -> AdaptiveMethod {
+        self.method
+    }
+}
+
 /// Related threshold functions for binarization
 pub trait Threshold {
     /// Binarize the image with the given threshold value
@@ -23,9 +122,34 @@ pub trait Threshold {
     /// automatic threshold!!.
     fn otsu_threshold(&mut self);
     /// Create histogram of the pixel values
-    fn get_histogram(&mut self) -> [usize; 256];
+    fn get_histogram(&self) -> [usize; 256];
     /// Analyze image histogram and calculated best threshold value
-    fn get_otsu_value(&mut self) -> u8;
+    fn get_otsu_value(&self) -> u8;
+    /// Binarize the image with the threshold value calculated with a global method
+    fn auto_threshold(&mut self, method: ThresholdMethod);
+    /// Analyze image histogram and calculate the threshold value of a global method
+    fn get_threshold_value(&self, method: ThresholdMethod) -> u8;
+    /// Analyze image histogram and calculate `k` increasing thresholds with multi-level Otsu's method,
+    /// `k` clamped to `1..=255`
+    fn get_multi_otsu_values(&self, k: usize) -> Vec<u8>;
+    /// Reduce the image to `k + 1` gray levels evenly spread from 0 to 255,
+    /// split by the thresholds of multi-level Otsu's method, `k` clamped to `1..=255`
+    fn multi_otsu_threshold(&mut self, k: usize);
+    /// Binarize each pixel with the threshold of the window around it
+    ///
+    /// The statistics of the window, clipped to the image, are computed
+    /// in constant time with integral images.
+    fn adaptive_threshold(&mut self, adaptive: AdaptiveThreshold);
+    /// Binarize with the mean of the `window` around each pixel minus `c`
+    fn mean_c_threshold(&mut self, window: u32, c: f64);
+    /// Binarize with the gaussian weighted mean of the `window` around each pixel minus `c`
+    fn gaussian_c_threshold(&mut self, window: u32, c: f64);
+    /// Binarize with Niblack's method, the mean plus `k` times the standard deviation of the `window`
+    fn niblack_threshold(&mut self, window: u32, k: f64);
+    /// Binarize with Sauvola's method, suited to documents with uneven lighting
+    fn sauvola_threshold(&mut self, window: u32, k: f64);
+    /// Binarize with Bradley and Roth's method, white when brighter than `1 - t` times the mean of the `window`
+    fn bradley_threshold(&mut self, window: u32, t: f64);
     /// Invert image pixels values
     fn invert(&mut self);
 }
@@ -36,7 +160,7 @@ impl Threshold for GrayImage {
             .for_each(|p| *p = if *p > threshold { 255 } else { 0 });
     }
 
-    fn get_histogram(&mut self) -> [usize; 256] {
+    fn get_histogram(&self) -> [usize; 256] {
         let mut out = [0; 256];
         self.iter().for_each(|p| {
             out[*p as usize] += 1;
@@ -44,34 +168,8 @@ impl Threshold for GrayImage {
         out
     }
 
-    fn get_otsu_value(&mut self) -> u8 {
-        let img_histogram: [usize; 256] = self.get_histogram();
-        let total_weight = self.width() as f64 * self.height() as f64;
-        let mut bg_sum = 0.0;
-        let mut bg_weight = 0.0;
-        let mut max_variance = 0.0;
-        let mut best_threshold = 0;
-        let sum_intensity: f64 = img_histogram
-            .iter()
-            .enumerate()
-            .fold(0f64, |acu, (t, c)| acu + (t * c) as f64);
-
-        for (threshold, count) in img_histogram.iter().enumerate() {
-            let fg_weight = total_weight - bg_weight;
-            if fg_weight > 0.0 && bg_weight > 0.0 {
-                let fg_mean = (sum_intensity - bg_sum) / fg_weight;
-                //let val = (bg_weight * fg_weight * ((bg_sum / bg_weight) - fg_mean)).powi(2);
-                let val = (bg_weight * fg_weight * ((bg_sum / bg_weight) - fg_mean)).powi(2);
-                if val >= max_variance {
-                    best_threshold = threshold as u8;
-                    max_variance = val;
-                }
-            }
-            bg_weight += *count as f64;
-            bg_sum += (threshold * count) as f64;
-        }
-
-        best_threshold
+    fn get_otsu_value(&self) -> u8 {
+        histogram::otsu_value(&self.get_histogram())
     }
 
     fn otsu_threshold(&mut self) {
@@ -81,11 +179,342 @@ impl Threshold for GrayImage {
             .for_each(|p| *p = if *p > threshold { 255 } else { 0 });
     }
 
+    fn auto_threshold(&mut self, method: ThresholdMethod) {
+        let threshold = self.get_threshold_value(method);
+        self.threshold(threshold);
+    }
+
+    fn get_threshold_value(&self, method: ThresholdMethod) -> u8 {
+        histogram::threshold_value(&self.get_histogram(), method)
+    }
+
+    fn get_multi_otsu_values(&self, k: usize) -> Vec<u8> {
+        histogram::multi_otsu_values(&self.get_histogram(), k)
+    }
+
+    fn multi_otsu_threshold(&mut self, k: usize) {
+        let thresholds = self.get_multi_otsu_values(k);
+        let levels = thresholds.len();
+        self.iter_mut().for_each(|p| {
+            let class = thresholds.iter().filter(|&&t| *p > t).count();
+            *p = (class * 255 / levels) as u8;
+        });
+    }
+
+    fn adaptive_threshold(&mut self, adaptive: AdaptiveThreshold) {
+        let (width, height) = self.dimensions();
+        let radius = adaptive.window / 2;
+        let k = adaptive.k;
+
+        let thresholds: Vec<f64> =
+        match adaptive.method {
+            AdaptiveMethod::GaussianC => {
+                gaussian_blur(self, radius)
+                    .into_iter()
+                    .map(|mean| mean - k)
+                    .collect()
+            },
+            method => {
+                let integral = IntegralImage::new(self);
+                let mut thresholds = Vec::with_capacity((width * height) as usize);
+                for y in 0..height {
+                    for x in 0..width {
+                        let (mean, deviation) = integral.window_statistics(x, y, radius);
+                        thresholds.push(match method {
+                            AdaptiveMethod::MeanC => mean - k,
+                            AdaptiveMethod::Niblack => mean + k * deviation,
+                            AdaptiveMethod::Sauvola => mean * (1.0 + k * (deviation / 128.0 - 1.0)),
+                            _ => mean * (1.0 - k),
+                        });
+                    }
+                }
+                thresholds
+            },
+        };
+
+        self.iter_mut()
+            .zip(thresholds)
+            .for_each(|(p, threshold)| *p = if *p as f64 > threshold { 255 } else { 0 });
+    }
+
+    fn mean_c_threshold(&mut self, window: u32, c: f64) {
+        self.adaptive_threshold(AdaptiveThreshold::new(AdaptiveMethod::MeanC).window(window).k(c));
+    }
+
+    fn gaussian_c_threshold(&mut self, window: u32, c: f64) {
+        self.adaptive_threshold(AdaptiveThreshold::new(AdaptiveMethod::GaussianC).window(window).k(c));
+    }
+
+    fn niblack_threshold(&mut self, window: u32, k: f64) {
+        self.adaptive_threshold(AdaptiveThreshold::new(AdaptiveMethod::Niblack).window(window).k(k));
+    }
+
+    fn sauvola_threshold(&mut self, window: u32, k: f64) {
+        self.adaptive_threshold(AdaptiveThreshold::new(AdaptiveMethod::Sauvola).window(window).k(k));
+    }
+
+    fn bradley_threshold(&mut self, window: u32, t: f64) {
+        self.adaptive_threshold(AdaptiveThreshold::new(AdaptiveMethod::Bradley).window(window).k(t));
+    }
+
     fn invert(&mut self) {
         self.iter_mut().for_each(|p| *p = 255 - *p);
     }
 }
 
+/// Summed area tables of the pixel values and of their squares
+struct IntegralImage {
+    width: u32,
+    height: u32,
+    sums: Vec<u64>,
+    squares: Vec<u64>,
+}
+
+impl IntegralImage {
+    /// Build the tables, with an extra zero row and column
+    fn new(image: &GrayImage) -> Self {
+        let (width, height) = image.dimensions();
+        let stride = (width + 1) as usize;
+        let mut sums = vec![0u64; stride * (height + 1) as usize];
+        let mut squares = vec![0u64; stride * (height + 1) as usize];
+
+        for y in 0..height as usize {
+            let (mut row_sum, mut row_square) = (0u64, 0u64);
+            for x in 0..width as usize {
+                let p = image.get_pixel(x as u32, y as u32)[0] as u64;
+                row_sum += p;
+                row_square += p * p;
+                sums[(y + 1) * stride + x + 1] = sums[y * stride + x + 1] + row_sum;
+                squares[(y + 1) * stride + x + 1] = squares[y * stride + x + 1] + row_square;
+            }
+        }
+
+        Self { width, height, sums, squares }
+    }
+
+    /// Get the mean and standard deviation of the window of `radius` around (x, y), clipped to the image
+    fn window_statistics(&self, x: u32, y: u32, radius: u32) -> (f64, f64) {
+        let stride = (self.width + 1) as usize;
+        let (x0, y0) = (x.saturating_sub(radius) as usize, y.saturating_sub(radius) as usize);
+        let (x1, y1) = ((x + radius + 1).min(self.width) as usize, (y + radius + 1).min(self.height) as usize);
+
+        let area = |table: &[u64]| table[y1 * stride + x1] + table[y0 * stride + x0] - table[y0 * stride + x1] - table[y1 * stride + x0];
+        let count = ((x1 - x0) * (y1 - y0)) as f64;
+        let mean = area(&self.sums) as f64 / count;
+        let variance = area(&self.squares) as f64 / count - mean * mean;
+
+        (mean, variance.max(0.0).sqrt())
+    }
+}
+
+/// Gaussian weighted mean of the window of `radius` around each pixel,
+/// replicating the border pixels
+///
+/// The standard deviation follows the size of the window as in OpenCV.
+fn gaussian_blur(image: &GrayImage, radius: u32) -> Vec<f64> {
+    let (width, height) = (image.width() as i64, image.height() as i64);
+    let radius = radius as i64;
+    let sigma = 0.3 * ((radius as f64) - 1.0) + 0.8;
+
+    let weights: Vec<f64> = (-radius..=radius).map(|d| (-((d * d) as f64) / (2.0 * sigma * sigma)).exp()).collect();
+    let total: f64 = weights.iter().sum();
+    let weights: Vec<f64> = weights.iter().map(|w| w / total).collect();
+
+    /* Separable convolution, rows then columns */
+    let mut rows = vec![0.0; (width * height) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            rows[(y * width + x) as usize] = (-radius..=radius)
+                .map(|d| weights[(d + radius) as usize] * image.get_pixel((x + d).clamp(0, width - 1) as u32, y as u32)[0] as f64)
+                .sum();
+        }
+    }
+
+    let mut blurred = vec![0.0; (width * height) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            blurred[(y * width + x) as usize] = (-radius..=radius)
+                .map(|d| weights[(d + radius) as usize] * rows[((y + d).clamp(0, height - 1) * width + x) as usize])
+                .sum();
+        }
+    }
+
+    blurred
+}
+
+/// Dithering methods for binarization
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dithering {
+    /// Floyd-Steinberg error diffusion
+    FloydSteinberg,
+    /// Atkinson error diffusion, diffusing only 3/4 of the error
+    Atkinson,
+    /// Jarvis-Judice-Ninke error diffusion
+    JarvisJudiceNinke,
+    /// Sierra error diffusion
+    Sierra,
+    /// Ordered dithering with the 2x2 Bayer matrix
+    Bayer2,
+    /// Ordered dithering with the 4x4 Bayer matrix
+    Bayer4,
+    /// Ordered dithering with the 8x8 Bayer matrix
+    Bayer8,
+}
+
+impl Dithering {
+    /// Get the error diffusion kernel and the divisor of its weights,
+    /// `None` for ordered dithering
+    pub fn kernel(&self) -> Option<(Kernel, i32)> {
+        match self {
+            Dithering::FloydSteinberg => Some((&[
+                                 (1, 0, 7),
+                (-1, 1, 3), (0, 1, 5), (1, 1, 1),
+            ], 16)),
+            Dithering::Atkinson => Some((&[
+                                 (1, 0, 1), (2, 0, 1),
+                (-1, 1, 1), (0, 1, 1), (1, 1, 1),
+                            (0, 2, 1),
+            ], 8)),
+            Dithering::JarvisJudiceNinke => Some((&[
+                                                     (1, 0, 7), (2, 0, 5),
+                (-2, 1, 3), (-1, 1, 5), (0, 1, 7), (1, 1, 5), (2, 1, 3),
+                (-2, 2, 1), (-1, 2, 3), (0, 2, 5), (1, 2, 3), (2, 2, 1),
+            ], 48)),
+            Dithering::Sierra => Some((&[
+                                                     (1, 0, 5), (2, 0, 3),
+                (-2, 1, 2), (-1, 1, 4), (0, 1, 5), (1, 1, 4), (2, 1, 2),
+                            (-1, 2, 2), (0, 2, 3), (1, 2, 2),
+            ], 32)),
+            Dithering::Bayer2 | Dithering::Bayer4 | Dithering::Bayer8 => None,
+        }
+    }
+
+    /// Get the size of the Bayer matrix, `None` for error diffusion
+    pub fn matrix_size(&self) -> Option<u32> {
+        match self {
+            Dithering::Bayer2 => Some(2),
+            Dithering::Bayer4 => Some(4),
+            Dithering::Bayer8 => Some(8),
+            _ => None,
+        }
+    }
+}
+
+/// Create the `size`x`size` Bayer matrix in row-major order,
+/// with every value from 0 to `size * size - 1`
+///
+/// `size` is rounded up to a power of two.
+pub fn bayer_matrix(size: u32) -> Vec<u32> {
+    let size = size.max(1).next_power_of_two();
+    let mut matrix = vec![0];
+    let mut n = 1;
+
+    /* M(2n) = | 4M     4M + 2 |
+     *         | 4M + 3 4M + 1 | */
+    while n < size {
+        let mut next = vec![0; (4 * n * n) as usize];
+        for y in 0..n {
+            for x in 0..n {
+                let m = 4 * matrix[(y * n + x) as usize];
+                next[(y * 2 * n + x) as usize] = m;
+                next[(y * 2 * n + x + n) as usize] = m + 2;
+                next[((y + n) * 2 * n + x) as usize] = m + 3;
+                next[((y + n) * 2 * n + x + n) as usize] = m + 1;
+            }
+        }
+        matrix = next;
+        n *= 2;
+    }
+
+    matrix
+}
+
+/// Dithering functions for binarization
+///
+/// Binarize the image spreading the quantization error, so the
+/// density of white pixels follows the midtones of the image.
+pub trait Dither {
+    /// Binarize the image with the given dithering method
+    fn dither(&mut self, dithering: Dithering);
+    /// Binarize the image diffusing the error with the Floyd-Steinberg kernel
+    fn floyd_steinberg(&mut self);
+    /// Binarize the image diffusing the error with the Atkinson kernel
+    fn atkinson(&mut self);
+    /// Binarize the image diffusing the error with the Jarvis-Judice-Ninke kernel
+    fn jarvis_judice_ninke(&mut self);
+    /// Binarize the image diffusing the error with the Sierra kernel
+    fn sierra(&mut self);
+    /// Binarize the image comparing each pixel with the `size`x`size` Bayer matrix
+    ///
+    /// `size` is rounded up to a power of two.
+    fn bayer(&mut self, size: u32);
+}
+
+impl Dither for GrayImage {
+    fn dither(&mut self, dithering: Dithering) {
+        if let Some((kernel, divisor)) = dithering.kernel() {
+            error_diffusion(self, kernel, divisor);
+        } else if let Some(size) = dithering.matrix_size() {
+            self.bayer(size);
+        }
+    }
+
+    fn floyd_steinberg(&mut self) {
+        self.dither(Dithering::FloydSteinberg);
+    }
+
+    fn atkinson(&mut self) {
+        self.dither(Dithering::Atkinson);
+    }
+
+    fn jarvis_judice_ninke(&mut self) {
+        self.dither(Dithering::JarvisJudiceNinke);
+    }
+
+    fn sierra(&mut self) {
+        self.dither(Dithering::Sierra);
+    }
+
+    fn bayer(&mut self, size: u32) {
+        let matrix = bayer_matrix(size);
+        let size = size.max(1).next_power_of_two();
+        let levels = (size * size) as f64;
+
+        for (x, y, p) in self.enumerate_pixels_mut() {
+            /* Thresholds evenly spread between 0 and 255 */
+            let threshold = (matrix[(y % size * size + x % size) as usize] as f64 + 0.5) * 256.0 / levels;
+            p[0] = if p[0] as f64 >= threshold { 255 } else { 0 };
+        }
+    }
+}
+
+/// Binarize the image at the middle value, spreading the error of each
+/// pixel to its neighbours with the weights of `kernel` divided by `divisor`
+fn error_diffusion(image: &mut GrayImage, kernel: &[(i32, i32, i32)], divisor: i32) {
+    let (width, height) = (image.width() as i32, image.height() as i32);
+    let mut values: Vec<i32> = image.iter().map(|&p| p as i32).collect();
+
+    for y in 0..height {
+        for x in 0..width {
+            let value = values[(y * width + x) as usize];
+            let new = if value > 127 { 255 } else { 0 };
+            let error = value - new;
+            values[(y * width + x) as usize] = new;
+
+            for &(dx, dy, weight) in kernel {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx >= 0 && nx < width && ny < height {
+                    values[(ny * width + nx) as usize] += error * weight / divisor;
+                }
+            }
+        }
+    }
+
+    image.iter_mut()
+        .zip(values)
+        .for_each(|(p, value)| *p = value as u8);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -112,4 +541,136 @@ mod tests {
         assert_eq!(otsu_value, 120);
     }
 
+    /// Fraction of white pixels of the image
+    fn white_fraction(img: &GrayImage) -> f64 {
+        img.iter().filter(|&&p| p == 255).count() as f64 / (img.width() * img.height()) as f64
+    }
+
+    #[test]
+    fn auto_threshold_methods() {
+        /* Dark strokes on a bright paper */
+        let mut img = GrayImage::from_fn(16, 16, |x, y| {
+            let noise = ((x * 7 + y * 3) % 16) as u8;
+            Luma([if x == y || x == 3 { 30 + noise } else { 184 + noise }])
+        });
+        let strokes: Vec<bool> = img.iter().map(|&p| p < 100).collect();
+
+        for method in ThresholdMethod::ALL {
+            let threshold = img.get_threshold_value(method);
+            assert!((45..=184).contains(&threshold), "{:?} {}", method, threshold);
+
+            let mut binary = img.clone();
+            binary.auto_threshold(method);
+            assert!(binary.iter().zip(strokes.iter()).filter(|(_, &stroke)| stroke).all(|(&p, _)| p == 0), "{:?}", method);
+            assert!(white_fraction(&binary) > 0.75, "{:?}", method);
+        }
+
+        /* Three levels of gray */
+        img.multi_otsu_threshold(2);
+        let mut levels: Vec<u8> = img.iter().copied().collect();
+        levels.sort_unstable();
+        levels.dedup();
+        assert_eq!(levels, [0, 127, 255]);
+    }
+
+    #[test]
+    fn bayer_matrix_values() {
+        assert_eq!(bayer_matrix(2), [0, 2, 3, 1]);
+        assert_eq!(bayer_matrix(4), [ 0,  8,  2, 10,
+                                     12,  4, 14,  6,
+                                      3, 11,  1,  9,
+                                     15,  7, 13,  5]);
+
+        let mut values = bayer_matrix(8);
+        values.sort_unstable();
+        assert_eq!(values, (0..64).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn dithering_keeps_midtones() {
+        let methods = [Dithering::FloydSteinberg, Dithering::Atkinson, Dithering::JarvisJudiceNinke,
+                       Dithering::Sierra, Dithering::Bayer2, Dithering::Bayer4, Dithering::Bayer8];
+
+        for method in methods {
+            for gray in [0u8, 64, 128, 192, 255] {
+                let mut img = GrayImage::from_pixel(32, 32, Luma([gray]));
+                img.dither(method);
+
+                assert!(img.iter().all(|&p| p == 0 || p == 255));
+                /* Atkinson loses a quarter of the error, darkening the midtones */
+                let tolerance = if method == Dithering::Atkinson { 0.15 } else { 0.05 };
+                let expected = gray as f64 / 255.0;
+                assert!((white_fraction(&img) - expected).abs() <= tolerance,
+                        "{:?} {} {}", method, gray, white_fraction(&img));
+            }
+        }
+    }
+
+    #[test]
+    fn floyd_steinberg_checkerboard() {
+        /* A uniform midtone becomes a checkerboard */
+        let mut img = GrayImage::from_pixel(4, 4, Luma([128]));
+        img.floyd_steinberg();
+
+        for (x, y, p) in img.enumerate_pixels() {
+            let expected = if (x + y) % 2 == 0 { 255 } else { 0 };
+            assert_eq!(p[0], expected, "({}, {})", x, y);
+        }
+    }
+
+    /// Text on a background lit from dark on the left to bright on the right
+    fn uneven_document() -> (GrayImage, Vec<bool>) {
+        let mut text = vec![];
+        let img = GrayImage::from_fn(64, 32, |x, y| {
+            let background = 60 + 3 * x;
+            let ink = x % 8 == 3 && y % 16 > 4 && y % 16 < 12;
+            text.push(ink);
+            Luma([if ink { background as u8 - 50 } else { background as u8 }])
+        });
+        (img, text)
+    }
+
+    #[test]
+    fn adaptive_threshold_uneven_lighting() {
+        let (img, text) = uneven_document();
+
+        /* A global threshold loses the text on the bright side */
+        let mut otsu = img.clone();
+        otsu.otsu_threshold();
+        let otsu_errors = otsu.iter().zip(text.iter()).filter(|(&p, &ink)| (p == 0) != ink).count();
+        assert!(otsu_errors > 100);
+
+        let methods = [AdaptiveMethod::MeanC, AdaptiveMethod::GaussianC, AdaptiveMethod::Niblack,
+                       AdaptiveMethod::Sauvola, AdaptiveMethod::Bradley];
+        for method in methods {
+            let mut adaptive = img.clone();
+            adaptive.adaptive_threshold(AdaptiveThreshold::new(method));
+
+            let errors = adaptive.iter().zip(text.iter()).filter(|(&p, &ink)| (p == 0) != ink).count();
+            assert!(errors < otsu_errors / 4, "{:?} {} {}", method, errors, otsu_errors);
+        }
+    }
+
+    #[test]
+    fn integral_image_statistics() {
+        let img = GrayImage::from_fn(5, 4, |x, y| Luma([(10 * x + y) as u8]));
+        let integral = IntegralImage::new(&img);
+
+        /* Whole 3x3 window */
+        let (mean, deviation) = integral.window_statistics(2, 1, 1);
+        assert!((mean - 21.0).abs() < 1e-9);
+        let variance = (0..3).flat_map(|y| (1..4).map(move |x| (10 * x + y) as f64))
+            .map(|p| (p - 21.0).powi(2)).sum::<f64>() / 9.0;
+        assert!((deviation - variance.sqrt()).abs() < 1e-9);
+
+        /* Window clipped to the top left corner */
+        let (mean, _) = integral.window_statistics(0, 0, 1);
+        assert!((mean - (0.0 + 1.0 + 10.0 + 11.0) / 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn gaussian_blur_keeps_uniform_images() {
+        let img = GrayImage::from_pixel(9, 7, Luma([77]));
+        assert!(gaussian_blur(&img, 3).iter().all(|&p| (p - 77.0).abs() < 1e-9));
+    }
 }
@@ -1,7 +1,24 @@
 //! Terminal Colors Abstraction
 //!
 //! Provides 256 terminal colors abstraction, providing:
-//! + Find best approximation to RGB color
+//! + Find best approximation to RGB color, on the 8, 16 or 256 colors palettes
+//! + Choose the distance metric used to compare colors
+//! + Cache the closest colors of a palette in a lookup table
+//! + Load the real colors of the terminal theme from a file
+//! + Build adaptive palettes for an image by median cut or k-means
+//! + Detect the color capability of the terminal
+
+pub mod metric;
+pub mod lut;
+pub mod theme;
+pub mod quantize;
+
+pub use metric::DistanceMetric;
+pub use lut::ColorLut;
+pub use theme::{Theme, ThemeError};
+pub use quantize::{AdaptivePalette, QuantizeMethod, Quantizer};
+
+use std::env;
 
 /// Maximum possible quadrance of RGB colors with 8-bit per channel.
 /// 
@@ -36,18 +53,31 @@ impl TermColor {
     /// Find the terminal color that minimizes the asimilarity of a general RGB color
     /// by a exahustive calculation of quadrances, return the TermColor which minimizes the quadrance.
     pub fn from(r: u8, g:u8, b:u8) -> Self {
+        Self::nearest(r, g, b, &TERM256_COLOR)
+    }
+
+    /// Create a TermColor from 24bit RGB color by finding the best approximate color
+    /// in the given Terminal colors palette
+    pub fn from_palette(r: u8, g:u8, b:u8, palette: TermPalette) -> Self {
+        Self::nearest(r, g, b, palette.colors())
+    }
+
+    /// Create a TermColor from 24bit RGB color by finding the closest color
+    /// in the given Terminal colors palette according to `metric`
+    ///
+    /// With [`DistanceMetric::Rgb`] it is the same as [`TermColor::from_palette`].
+    pub fn closest(r: u8, g:u8, b:u8, palette: TermPalette, metric: DistanceMetric) -> Self {
+        theme::xterm_theme().closest(r, g, b, palette, metric)
+    }
+
+    /// Find the color of `colors` which minimizes the quadrance,
+    /// where each color index is its position in `colors`
+    fn nearest(r: u8, g:u8, b:u8, colors: &[(u8, u8, u8)]) -> Self {
         let mut min = MAX_QUADRANCE;
-        let mut tcolor = Self::default();
-        tcolor.r = r;
-        tcolor.g = g;
-        tcolor.b = b;
-
-        for (i, color) in TERM256_COLOR.iter().enumerate() {
-            let mut termcolor = Self::default();
-            termcolor.r = color.0;
-            termcolor.g = color.1;
-            termcolor.b = color.2;
-            termcolor.index = i as u8;
+        let mut tcolor = Self { r, g, b, index: 0 };
+
+        for (i, color) in colors.iter().enumerate() {
+            let termcolor = Self { r: color.0, g: color.1, b: color.2, index: i as u8 };
             
             let q = tcolor.quadrance(&termcolor);
 
@@ -78,6 +108,138 @@ impl TermColor {
     }
 }
 
+/// Palette of Terminal colors
+///
+/// Each palette is a prefix of the 256 Terminal colors, so a color index
+/// is the same on every palette containing it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TermPalette {
+    /// Basic colors, SGR 30–37 and 40–47
+    Ansi8,
+    /// Basic and bright colors, SGR 30–37, 90–97 and 40–47, 100–107
+    Ansi16,
+    /// 256 colors, SGR 38;5 and 48;5
+    #[default]
+    Ansi256,
+}
+
+impl TermPalette {
+    /// Get the RGB value of the palette colors ordered by index
+    pub fn colors(&self) -> &'static [(u8, u8, u8)] {
+        &TERM256_COLOR[..self.size()]
+    }
+
+    /// Get the number of colors of the palette
+    pub fn size(&self) -> usize {
+        match self {
+            TermPalette::Ansi8 => 8,
+            TermPalette::Ansi16 => 16,
+            TermPalette::Ansi256 => 256,
+        }
+    }
+
+    /// Check if the color index belongs to the palette
+    pub fn contains(&self, index: u8) -> bool {
+        (index as usize) < self.size()
+    }
+}
+
+/// Color capability of a terminal
+///
+/// Ordered from no color to true color, so capabilities can be compared.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ColorDepth {
+    /// No color
+    None,
+    /// First 8 terminal colors (3-bit)
+    Ansi8,
+    /// First 16 terminal colors (4-bit)
+    Ansi16,
+    /// 256 terminal colors (8-bit)
+    Ansi256,
+    /// True color (24-bit)
+    #[default]
+    Truecolor,
+}
+
+impl ColorDepth {
+    /// Get the palette of Terminal colors of the capability,
+    /// `None` for no color and true color
+    pub fn palette(&self) -> Option<TermPalette> {
+        match self {
+            ColorDepth::Ansi8 => Some(TermPalette::Ansi8),
+            ColorDepth::Ansi16 => Some(TermPalette::Ansi16),
+            ColorDepth::Ansi256 => Some(TermPalette::Ansi256),
+            ColorDepth::None | ColorDepth::Truecolor => None,
+        }
+    }
+
+    /// Get the color capability given the values of the environment variables
+    /// `COLORTERM`, `TERM`, `NO_COLOR` and `CLICOLOR_FORCE`
+    ///
+    /// + A non empty `NO_COLOR` disables colors.
+    /// + `COLORTERM` set to `truecolor` or `24bit`, or a `TERM` for direct colors, gives true color.
+    /// + A `TERM` ending with `256color` gives 256 colors, otherwise any `TERM` but `dumb` gives 16 colors.
+    /// + rxvt terminals are limited to 256 colors, as they can not show true color.
+    /// + `CLICOLOR_FORCE` set to anything but `0` gives at least 16 colors.
+    pub fn from_vars(colorterm: Option<&str>, term: Option<&str>, no_color: Option<&str>, clicolor_force: Option<&str>) -> Self {
+        if no_color.is_some_and(|value| !value.is_empty()) {
+            return Self::None;
+        }
+
+        let colorterm = colorterm.unwrap_or("").to_ascii_lowercase();
+        let term = term.unwrap_or("").to_ascii_lowercase();
+        let rxvt = term.starts_with("rxvt");
+
+        let depth =
+        if colorterm == "truecolor" || colorterm == "24bit" ||
+           term.ends_with("-direct") || term.contains("truecolor") {
+            Self::Truecolor
+        }
+        else if term.ends_with("256color") || rxvt {
+            Self::Ansi256
+        }
+        else if term.is_empty() || term == "dumb" {
+            Self::None
+        }
+        else {
+            Self::Ansi16
+        };
+
+        let depth = if rxvt { depth.min(Self::Ansi256) } else { depth };
+
+        if clicolor_force.is_some_and(|value| !value.is_empty() && value != "0") {
+            depth.max(Self::Ansi16)
+        } else {
+            depth
+        }
+    }
+}
+
+/// Detect the color capability of the terminal from the environment
+///
+/// See [`ColorDepth::from_vars`] for the rules applied.
+pub fn detect_color_depth() -> ColorDepth {
+    let var = |name: &str| env::var(name).ok();
+
+    ColorDepth::from_vars(
+        var("COLORTERM").as_deref(),
+        var("TERM").as_deref(),
+        var("NO_COLOR").as_deref(),
+        var("CLICOLOR_FORCE").as_deref(),
+        )
+}
+
+/// Check if colors are disabled by a non empty `NO_COLOR`
+pub fn no_color() -> bool {
+    env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+/// Check if colors are forced, even when not writing to a terminal,
+/// by `CLICOLOR_FORCE` set to anything but `0`
+pub fn clicolor_force() -> bool {
+    env::var_os("CLICOLOR_FORCE").is_some_and(|value| !value.is_empty() && value != "0")
+}
 
 
 /// Array of 256 Terminal Colors with their RGB (24-bit) equivalent,
@@ -345,7 +507,7 @@ pub const TERM256_COLOR: [(u8, u8, u8); 256] = [
 
 #[cfg(test)]
 mod tests {
-    use super::TermColor;
+    use super::{ColorDepth, DistanceMetric, TermColor, TermPalette};
 
     #[test]
     fn quadrance_equal() {
@@ -383,4 +545,87 @@ mod tests {
 
         assert!(q1-q2 > 0.0);
     }
+
+    #[test]
+    fn palette_nearest() {
+        /* Bright red only on 16 colors */
+        assert_eq!(TermColor::from_palette(250, 10, 5, TermPalette::Ansi16).index, 9);
+        assert_eq!(TermColor::from_palette(250, 10, 5, TermPalette::Ansi8).index, 1);
+        assert_eq!(TermColor::from_palette(0x5F, 0x87, 0xFF, TermPalette::Ansi256).index, 69);
+
+        for palette in [TermPalette::Ansi8, TermPalette::Ansi16, TermPalette::Ansi256] {
+            let c = TermColor::from_palette(200, 30, 170, palette);
+            assert!(palette.contains(c.index));
+        }
+        assert_eq!(TermColor::from_palette(1, 2, 3, TermPalette::Ansi256).index, TermColor::from(1, 2, 3).index);
+    }
+
+    #[test]
+    fn color_depth_from_vars() {
+        let depth = |colorterm, term, no_color, force| ColorDepth::from_vars(colorterm, term, no_color, force);
+
+        assert_eq!(depth(Some("truecolor"), Some("xterm-256color"), None, None), ColorDepth::Truecolor);
+        assert_eq!(depth(Some("24bit"), None, None, None), ColorDepth::Truecolor);
+        assert_eq!(depth(None, Some("xterm-direct"), None, None), ColorDepth::Truecolor);
+        assert_eq!(depth(None, Some("xterm-256color"), None, None), ColorDepth::Ansi256);
+        assert_eq!(depth(None, Some("xterm"), None, None), ColorDepth::Ansi16);
+        assert_eq!(depth(None, Some("linux"), None, None), ColorDepth::Ansi16);
+        assert_eq!(depth(None, Some("dumb"), None, None), ColorDepth::None);
+        assert_eq!(depth(None, None, None, None), ColorDepth::None);
+
+        /* rxvt can not show true color */
+        assert_eq!(depth(Some("truecolor"), Some("rxvt-unicode-256color"), None, None), ColorDepth::Ansi256);
+        assert_eq!(depth(None, Some("rxvt-unicode"), None, None), ColorDepth::Ansi256);
+
+        /* NO_COLOR wins over everything, an empty one is ignored */
+        assert_eq!(depth(Some("truecolor"), Some("xterm"), Some("1"), Some("1")), ColorDepth::None);
+        assert_eq!(depth(None, Some("xterm-256color"), Some(""), None), ColorDepth::Ansi256);
+
+        /* CLICOLOR_FORCE */
+        assert_eq!(depth(None, Some("dumb"), None, Some("1")), ColorDepth::Ansi16);
+        assert_eq!(depth(None, None, None, Some("0")), ColorDepth::None);
+        assert_eq!(depth(None, Some("xterm-256color"), None, Some("1")), ColorDepth::Ansi256);
+    }
+
+    #[test]
+    fn closest_by_metric() {
+        /* RGB matches the exhaustive quadrance search */
+        for rgb in [(1, 2, 3), (200, 30, 170), (90, 60, 20)] {
+            assert_eq!(TermColor::closest(rgb.0, rgb.1, rgb.2, TermPalette::Ansi256, DistanceMetric::Rgb).index,
+                       TermColor::from(rgb.0, rgb.1, rgb.2).index);
+        }
+
+        /* Exact palette colors are found by every metric */
+        for metric in [DistanceMetric::Rgb, DistanceMetric::Redmean, DistanceMetric::Cie76, DistanceMetric::Ciede2000] {
+            assert_eq!(TermColor::closest(0x5F, 0x87, 0xFF, TermPalette::Ansi256, metric).index, 69);
+            assert_eq!(TermColor::closest(0x80, 0x00, 0x00, TermPalette::Ansi8, metric).index, 1);
+            assert!(TermPalette::Ansi16.contains(TermColor::closest(30, 200, 170, TermPalette::Ansi16, metric).index));
+        }
+    }
+
+    #[test]
+    fn closest_tricky_colors() {
+        let closest = |rgb: (u8, u8, u8), palette, metric| TermColor::closest(rgb.0, rgb.1, rgb.2, palette, metric).index;
+
+        /* Dark navy and dark green fall to the grayscale ramp by RGB, CIEDE2000 keeps their hue */
+        assert_eq!(closest((20, 20, 60), TermPalette::Ansi256, DistanceMetric::Rgb), 235);
+        assert_eq!(closest((20, 20, 60), TermPalette::Ansi256, DistanceMetric::Ciede2000), 17);
+        assert_eq!(closest((0, 40, 0), TermPalette::Ansi256, DistanceMetric::Rgb), 233);
+        assert_eq!(closest((0, 40, 0), TermPalette::Ansi256, DistanceMetric::Ciede2000), 22);
+
+        /* Orange becomes olive by RGB and redmean, bright red on CIELAB */
+        assert_eq!(closest((255, 128, 0), TermPalette::Ansi16, DistanceMetric::Rgb), 3);
+        assert_eq!(closest((255, 128, 0), TermPalette::Ansi16, DistanceMetric::Redmean), 3);
+        assert_eq!(closest((255, 128, 0), TermPalette::Ansi16, DistanceMetric::Cie76), 9);
+        assert_eq!(closest((255, 128, 0), TermPalette::Ansi16, DistanceMetric::Ciede2000), 9);
+
+        /* Hot pink becomes gray by RGB, bright magenta by CIEDE2000 */
+        assert_eq!(closest((255, 105, 180), TermPalette::Ansi16, DistanceMetric::Rgb), 7);
+        assert_eq!(closest((255, 105, 180), TermPalette::Ansi16, DistanceMetric::Ciede2000), 13);
+
+        /* Skin tone, every metric agrees */
+        for metric in [DistanceMetric::Rgb, DistanceMetric::Redmean, DistanceMetric::Cie76, DistanceMetric::Ciede2000] {
+            assert_eq!(closest((232, 190, 172), TermPalette::Ansi256, metric), 181);
+        }
+    }
 }
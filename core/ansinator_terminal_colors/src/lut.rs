@@ -0,0 +1,290 @@
+//! Lookup table of Terminal colors
+//!
+//! Splits the RGB cube in 32x32x32 boxes, keeping for each box only the
+//! palette colors which can be the closest to some color inside it,
+//! so finding the closest color compares a few candidates instead of
+//! the whole palette, with the same result as the exhaustive search.
+//! CIELAB metrics instead memoize the exhaustive search of each color of a box.
+
+use crate::metric::{self, DistanceMetric};
+use crate::theme::{self, Theme};
+use crate::{TermColor, TermPalette};
+use std::fmt;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::{Arc, OnceLock};
+
+/// Number of boxes along each channel
+const BOXES: usize = 32;
+
+/// Number of channel values of each box
+const BOX_SIZE: usize = 256 / BOXES;
+
+/// Closest color index plus one of each color of a box, zero until searched
+type BoxMemo = [AtomicU16; BOX_SIZE * BOX_SIZE * BOX_SIZE];
+
+/// Lookup table of the closest Terminal color of a palette
+///
+/// Built once for a theme, a palette and a metric, the results are identical to
+/// [`Theme::closest`]. CIELAB metrics are not bounded on RGB boxes,
+/// so each color keeps the exhaustive search, done once and memoized on its box.
+#[derive(Clone)]
+pub struct ColorLut {
+    theme: Theme,
+    palette: TermPalette,
+    metric: DistanceMetric,
+    /// Start of the candidates of each box, plus the end of the last one
+    offsets: Vec<u32>,
+    /// Candidates of every box, ordered by index
+    candidates: Vec<u8>,
+    /// Memo of every box for CIELAB metrics, allocated on the first search inside the box
+    memo: Arc<[OnceLock<Box<BoxMemo>>]>,
+}
+
+impl fmt::Debug for ColorLut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ColorLut")
+            .field("palette", &self.palette)
+            .field("metric", &self.metric)
+            .field("candidates", &self.candidates.len())
+            .field("memo", &self.memo.iter().filter(|memo| memo.get().is_some()).count())
+            .finish()
+    }
+}
+
+impl ColorLut {
+    /// Build the lookup table of `palette` for `metric` with the xterm colors
+    pub fn new(palette: TermPalette, metric: DistanceMetric) -> Self {
+        Self::with_theme(theme::xterm_theme(), palette, metric)
+    }
+
+    /// Build the lookup table of `palette` for `metric` with the colors of `theme`
+    pub fn with_theme(theme: &Theme, palette: TermPalette, metric: DistanceMetric) -> Self {
+        let mut lut = Self { theme: theme.clone(), palette, metric, offsets: vec![0], candidates: vec![], memo: Arc::new([]) };
+
+        /* Channel weights bounding the quadrance of the metric */
+        let (min_weights, max_weights) = match metric {
+            DistanceMetric::Rgb => ([1.0, 1.0, 1.0], [1.0, 1.0, 1.0]),
+            DistanceMetric::Redmean => ([2.0, 4.0, 2.0], [3.0, 4.0, 3.0]),
+            DistanceMetric::Cie76 | DistanceMetric::Ciede2000 => {
+                lut.memo = (0..BOXES * BOXES * BOXES).map(|_| OnceLock::new()).collect();
+                return lut;
+            },
+        };
+        let colors = theme.colors(palette);
+
+        /* Squared distances from the values of each box to the nearest and farthest channel value of each color */
+        let channel_distances = |channel: fn(&(u8, u8, u8)) -> u8| -> Vec<Vec<(f64, f64)>> {
+            (0..BOXES).map(|b| {
+                let (low, high) = ((b * BOX_SIZE) as f64, (b * BOX_SIZE + BOX_SIZE - 1) as f64);
+                colors.iter().map(|color| {
+                    let value = channel(color) as f64;
+                    let nearest = if value < low { low - value } else if value > high { value - high } else { 0.0 };
+                    let farthest = f64::max(value - low, high - value);
+                    (nearest * nearest, farthest * farthest)
+                }).collect()
+            }).collect()
+        };
+        let reds = channel_distances(|color| color.0);
+        let greens = channel_distances(|color| color.1);
+        let blues = channel_distances(|color| color.2);
+
+        lut.offsets.reserve(BOXES * BOXES * BOXES);
+        for red in reds.iter() {
+            for green in greens.iter() {
+                for blue in blues.iter() {
+                    /* The closest color is never farther than the farthest point of the box to any color */
+                    let farthest = (0..colors.len())
+                        .map(|i| max_weights[0] * red[i].1 + max_weights[1] * green[i].1 + max_weights[2] * blue[i].1)
+                        .fold(f64::INFINITY, f64::min);
+
+                    for i in 0..colors.len() {
+                        if min_weights[0] * red[i].0 + min_weights[1] * green[i].0 + min_weights[2] * blue[i].0 <= farthest {
+                            lut.candidates.push(i as u8);
+                        }
+                    }
+                    lut.offsets.push(lut.candidates.len() as u32);
+                }
+            }
+        }
+
+        lut
+    }
+
+    /// Get the theme of the lookup table
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Get the palette of the lookup table
+    pub fn palette(&self) -> TermPalette {
+        self.palette
+    }
+
+    /// Get the metric of the lookup table
+    pub fn metric(&self) -> DistanceMetric {
+        self.metric
+    }
+
+    /// Find the closest color of the palette to a 24bit RGB color
+    pub fn closest(&self, r: u8, g: u8, b: u8) -> TermColor {
+        TermColor { r, g, b, index: self.index(r, g, b) }
+    }
+
+    /// Find the index of the closest color of the palette to a 24bit RGB color
+    pub fn index(&self, r: u8, g: u8, b: u8) -> u8 {
+        let i = (r as usize / BOX_SIZE * BOXES + g as usize / BOX_SIZE) * BOXES + b as usize / BOX_SIZE;
+
+        if self.metric.is_lab() {
+            let memo = self.memo[i].get_or_init(|| Box::new(std::array::from_fn(|_| AtomicU16::new(0))));
+            let j = (r as usize % BOX_SIZE * BOX_SIZE + g as usize % BOX_SIZE) * BOX_SIZE + b as usize % BOX_SIZE;
+            return match memo[j].load(Ordering::Relaxed) {
+                0 => {
+                    let index = self.theme.closest(r, g, b, self.palette, self.metric).index;
+                    memo[j].store(index as u16 + 1, Ordering::Relaxed);
+                    index
+                },
+                known => (known - 1) as u8,
+            };
+        }
+
+        let rgb = (r, g, b);
+        let colors = self.theme.colors(self.palette);
+        let candidates = &self.candidates[self.offsets[i] as usize..self.offsets[i + 1] as usize];
+
+        let mut min = f64::INFINITY;
+        let mut index = 0;
+        for &candidate in candidates {
+            let color = colors[candidate as usize];
+            let q = match self.metric {
+                DistanceMetric::Redmean => metric::redmean_quadrance(rgb, color),
+                _ => metric::rgb_quadrance(rgb, color),
+            };
+            if q < min {
+                min = q;
+                index = candidate;
+            }
+        }
+
+        index
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// Colors sampled on a grid of the RGB cube, including its corners
+    fn samples(step: usize) -> Vec<(u8, u8, u8)> {
+        let values: Vec<u8> = (0..256).step_by(step).chain(std::iter::once(255)).map(|v| v as u8).collect();
+        let mut colors = vec![];
+        for &r in values.iter() {
+            for &g in values.iter() {
+                for &b in values.iter() {
+                    colors.push((r, g, b));
+                }
+            }
+        }
+        colors
+    }
+
+    #[test]
+    fn identical_to_exhaustive() {
+        let colors = samples(11);
+
+        for palette in [TermPalette::Ansi8, TermPalette::Ansi16, TermPalette::Ansi256] {
+            for metric in [DistanceMetric::Rgb, DistanceMetric::Redmean] {
+                let lut = ColorLut::new(palette, metric);
+                for &(r, g, b) in colors.iter() {
+                    assert_eq!(lut.index(r, g, b), TermColor::closest(r, g, b, palette, metric).index,
+                               "{:?} {:?} ({}, {}, {})", palette, metric, r, g, b);
+                }
+            }
+        }
+
+        /* Every color of a box edge, where boxes meet */
+        let lut = ColorLut::new(TermPalette::Ansi256, DistanceMetric::Rgb);
+        for v in 0..=255 {
+            assert_eq!(lut.index(v, 255 - v, 7), TermColor::from(v, 255 - v, 7).index);
+            assert_eq!(lut.index(8, v, 15), TermColor::from(8, v, 15).index);
+        }
+    }
+
+    #[test]
+    fn identical_with_theme() {
+        let theme = Theme::from_colors(&[(0, 43, 54), (220, 50, 47), (133, 153, 0), (181, 137, 0), (38, 139, 210), (211, 54, 130)]);
+        let lut = ColorLut::with_theme(&theme, TermPalette::Ansi16, DistanceMetric::Redmean);
+
+        for &(r, g, b) in samples(17).iter() {
+            assert_eq!(lut.index(r, g, b), theme.closest(r, g, b, TermPalette::Ansi16, DistanceMetric::Redmean).index);
+        }
+    }
+
+    #[test]
+    fn lab_metrics_memoized() {
+        for metric in [DistanceMetric::Cie76, DistanceMetric::Ciede2000] {
+            let lut = ColorLut::new(TermPalette::Ansi16, metric);
+            for &(r, g, b) in samples(17).iter() {
+                assert_eq!(lut.index(r, g, b), TermColor::closest(r, g, b, TermPalette::Ansi16, metric).index);
+            }
+            /* Searched again from the memo */
+            for &(r, g, b) in samples(17).iter() {
+                assert_eq!(lut.index(r, g, b), TermColor::closest(r, g, b, TermPalette::Ansi16, metric).index);
+            }
+        }
+
+        let lut = ColorLut::new(TermPalette::Ansi256, DistanceMetric::Ciede2000);
+        assert_eq!(lut.index(255, 128, 0), lut.index(255, 128, 0));
+        assert_eq!(lut.closest(20, 20, 60).index, TermColor::closest(20, 20, 60, TermPalette::Ansi256, DistanceMetric::Ciede2000).index);
+        assert_eq!(lut.memo.iter().filter(|memo| memo.get().is_some()).count(), 2);
+    }
+
+    #[test]
+    fn fewer_candidates() {
+        /* Each box compares a few candidates instead of the 256 colors of the exhaustive search */
+        for metric in [DistanceMetric::Rgb, DistanceMetric::Redmean] {
+            let lut = ColorLut::new(TermPalette::Ansi256, metric);
+            let counts: Vec<u32> = lut.offsets.windows(2).map(|offsets| offsets[1] - offsets[0]).collect();
+
+            assert_eq!(counts.len(), BOXES * BOXES * BOXES);
+            assert!(counts.iter().all(|&count| (1..32).contains(&count)), "{:?}", metric);
+            assert!(lut.candidates.len() < 8 * counts.len(), "{:?}", metric);
+        }
+    }
+
+    /// Time the lookup table against the exhaustive search over the pixels of a 640x480 image,
+    /// run with `cargo test --release -- --ignored --nocapture benchmark_speedup`
+    #[test]
+    #[ignore]
+    fn benchmark_speedup() {
+        /* Smooth gradients with some noise, repeating colors like a photograph */
+        let mut seed: u32 = 1;
+        let mut noise = move || {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) as u8 % 16
+        };
+        let pixels: Vec<(u8, u8, u8)> = (0..480u32).flat_map(|y| (0..640u32).map(move |x| (x, y)))
+            .map(|(x, y)| ((x * 240 / 640) as u8 + noise(), (y * 240 / 480) as u8 + noise(), ((x + y) * 240 / 1120) as u8 + noise()))
+            .collect();
+
+        for metric in [DistanceMetric::Rgb, DistanceMetric::Redmean, DistanceMetric::Ciede2000] {
+            let start = Instant::now();
+            let exhaustive: Vec<u8> = pixels.iter()
+                .map(|&(r, g, b)| TermColor::closest(r, g, b, TermPalette::Ansi256, metric).index)
+                .collect();
+            let exhaustive_time = start.elapsed();
+
+            let start = Instant::now();
+            let lut = ColorLut::new(TermPalette::Ansi256, metric);
+            let build_time = start.elapsed();
+            let cached: Vec<u8> = pixels.iter().map(|&(r, g, b)| lut.index(r, g, b)).collect();
+            let cached_time = start.elapsed();
+
+            assert_eq!(exhaustive, cached, "{:?}", metric);
+            println!("{:?} on {} pixels: exhaustive {:?}, lookup table {:?} (built in {:?}), {:.1}x faster",
+                     metric, pixels.len(), exhaustive_time, cached_time, build_time,
+                     exhaustive_time.as_secs_f64() / cached_time.as_secs_f64());
+        }
+    }
+}
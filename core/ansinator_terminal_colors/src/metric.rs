@@ -0,0 +1,228 @@
+//! Color distance metrics
+//!
+//! Provides the metrics used to find the best approximation of a color:
+//! + RGB euclidean distance
+//! + Weighted RGB "redmean" distance
+//! + CIE76 distance on the CIELAB color space
+//! + CIEDE2000 distance on the CIELAB color space
+
+use std::f64::consts::PI;
+
+/// Color in the CIELAB color space, as `(L*, a*, b*)`
+pub type Lab = (f64, f64, f64);
+
+/// Metric measuring how different two RGB colors look
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DistanceMetric {
+    /// Euclidean distance of the RGB channels
+    #[default]
+    Rgb,
+    /// Euclidean distance of the RGB channels weighted by the mean red value
+    ///
+    /// <https://www.compuphase.com/cmetric.htm>
+    Redmean,
+    /// Euclidean distance on the CIELAB color space
+    Cie76,
+    /// CIEDE2000 color difference on the CIELAB color space
+    ///
+    /// <https://en.wikipedia.org/wiki/Color_difference#CIEDE2000>
+    Ciede2000,
+}
+
+impl DistanceMetric {
+    /// Get the distance between two RGB colors, zero when equal
+    pub fn distance(&self, c1: (u8, u8, u8), c2: (u8, u8, u8)) -> f64 {
+        match self {
+            DistanceMetric::Rgb => rgb_quadrance(c1, c2).sqrt(),
+            DistanceMetric::Redmean => redmean_quadrance(c1, c2).sqrt(),
+            DistanceMetric::Cie76 => cie76(rgb_to_lab(c1), rgb_to_lab(c2)),
+            DistanceMetric::Ciede2000 => ciede2000(rgb_to_lab(c1), rgb_to_lab(c2)),
+        }
+    }
+
+    /// Check if the metric works on the CIELAB color space
+    pub fn is_lab(&self) -> bool {
+        matches!(self, DistanceMetric::Cie76 | DistanceMetric::Ciede2000)
+    }
+}
+
+/// Sum of the squares of the differences of each channel
+pub fn rgb_quadrance(c1: (u8, u8, u8), c2: (u8, u8, u8)) -> f64 {
+    f64::powi(c1.0 as f64 - c2.0 as f64, 2) +
+    f64::powi(c1.1 as f64 - c2.1 as f64, 2) +
+    f64::powi(c1.2 as f64 - c2.2 as f64, 2)
+}
+
+/// Sum of the squares of the differences of each channel, weighted by the mean red value
+pub fn redmean_quadrance(c1: (u8, u8, u8), c2: (u8, u8, u8)) -> f64 {
+    let redmean = (c1.0 as f64 + c2.0 as f64) / 2.0;
+
+    (2.0 + redmean / 256.0) * f64::powi(c1.0 as f64 - c2.0 as f64, 2) +
+    4.0 * f64::powi(c1.1 as f64 - c2.1 as f64, 2) +
+    (2.0 + (255.0 - redmean) / 256.0) * f64::powi(c1.2 as f64 - c2.2 as f64, 2)
+}
+
+/// Convert a sRGB color to CIELAB, with D65 white point
+pub fn rgb_to_lab(rgb: (u8, u8, u8)) -> Lab {
+    /* sRGB to linear RGB */
+    let linear = |c: u8| {
+        let c = c as f64 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            f64::powf((c + 0.055) / 1.055, 2.4)
+        }
+    };
+    let (r, g, b) = (linear(rgb.0), linear(rgb.1), linear(rgb.2));
+
+    /* Linear RGB to XYZ, normalized by the D65 white point */
+    let x = (0.4124564 * r + 0.3575761 * g + 0.1804375 * b) / 0.95047;
+    let y = 0.2126729 * r + 0.7151522 * g + 0.0721750 * b;
+    let z = (0.0193339 * r + 0.1191920 * g + 0.9503041 * b) / 1.08883;
+
+    /* XYZ to Lab */
+    let f = |t: f64| {
+        if t > 216.0 / 24389.0 {
+            t.cbrt()
+        } else {
+            (24389.0 / 27.0 * t + 16.0) / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+
+    (116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+}
+
+/// CIE76 color difference, the euclidean distance on CIELAB
+pub fn cie76(lab1: Lab, lab2: Lab) -> f64 {
+    (f64::powi(lab1.0 - lab2.0, 2) +
+     f64::powi(lab1.1 - lab2.1, 2) +
+     f64::powi(lab1.2 - lab2.2, 2)).sqrt()
+}
+
+/// CIEDE2000 color difference, with unity parametric weighting factors
+///
+/// Implemented as in "The CIEDE2000 Color-Difference Formula: Implementation Notes,
+/// Supplementary Test Data, and Mathematical Observations" by G. Sharma, W. Wu and E. N. Dalal.
+pub fn ciede2000(lab1: Lab, lab2: Lab) -> f64 {
+    let (l1, a1, b1) = lab1;
+    let (l2, a2, b2) = lab2;
+
+    /* Adjust a* to compensate the neutral colors */
+    let c1 = a1.hypot(b1);
+    let c2 = a2.hypot(b2);
+    let c_mean7 = f64::powi((c1 + c2) / 2.0, 7);
+    let g = 0.5 * (1.0 - (c_mean7 / (c_mean7 + f64::powi(25.0, 7))).sqrt());
+    let a1 = (1.0 + g) * a1;
+    let a2 = (1.0 + g) * a2;
+
+    let c1 = a1.hypot(b1);
+    let c2 = a2.hypot(b2);
+    let hue = |a: f64, b: f64| {
+        if a == 0.0 && b == 0.0 {
+            0.0
+        } else {
+            b.atan2(a).rem_euclid(2.0 * PI)
+        }
+    };
+    let h1 = hue(a1, b1);
+    let h2 = hue(a2, b2);
+
+    /* Differences of lightness, chroma and hue */
+    let dl = l2 - l1;
+    let dc = c2 - c1;
+    let dh =
+    if c1 * c2 == 0.0 {
+        0.0
+    } else if (h2 - h1).abs() <= PI {
+        h2 - h1
+    } else if h2 - h1 > PI {
+        h2 - h1 - 2.0 * PI
+    } else {
+        h2 - h1 + 2.0 * PI
+    };
+    let dh = 2.0 * (c1 * c2).sqrt() * (dh / 2.0).sin();
+
+    /* Means of lightness, chroma and hue */
+    let l_mean = (l1 + l2) / 2.0;
+    let c_mean = (c1 + c2) / 2.0;
+    let h_mean =
+    if c1 * c2 == 0.0 {
+        h1 + h2
+    } else if (h1 - h2).abs() <= PI {
+        (h1 + h2) / 2.0
+    } else if h1 + h2 < 2.0 * PI {
+        (h1 + h2 + 2.0 * PI) / 2.0
+    } else {
+        (h1 + h2 - 2.0 * PI) / 2.0
+    };
+
+    /* Weighting functions */
+    let t = 1.0
+        - 0.17 * (h_mean - PI / 6.0).cos()
+        + 0.24 * (2.0 * h_mean).cos()
+        + 0.32 * (3.0 * h_mean + PI / 30.0).cos()
+        - 0.20 * (4.0 * h_mean - 63.0 * PI / 180.0).cos();
+    let sl = 1.0 + 0.015 * f64::powi(l_mean - 50.0, 2) / (20.0 + f64::powi(l_mean - 50.0, 2)).sqrt();
+    let sc = 1.0 + 0.045 * c_mean;
+    let sh = 1.0 + 0.015 * c_mean * t;
+
+    /* Rotation term for the blue region */
+    let d_theta = PI / 6.0 * (-f64::powi((h_mean.to_degrees() - 275.0) / 25.0, 2)).exp();
+    let c_mean7 = f64::powi(c_mean, 7);
+    let rc = 2.0 * (c_mean7 / (c_mean7 + f64::powi(25.0, 7))).sqrt();
+    let rt = -rc * (2.0 * d_theta).sin();
+
+    (f64::powi(dl / sl, 2) +
+     f64::powi(dc / sc, 2) +
+     f64::powi(dh / sh, 2) +
+     rt * (dc / sc) * (dh / sh)).sqrt()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(value: f64, expected: f64) {
+        assert!((value - expected).abs() < 1e-4, "{} != {}", value, expected);
+    }
+
+    #[test]
+    fn lab_reference_values() {
+        let (l, a, b) = rgb_to_lab((255, 255, 255));
+        assert_close(l, 100.0);
+        assert!(a.abs() < 1e-3 && b.abs() < 1e-3);
+
+        let (l, a, b) = rgb_to_lab((255, 0, 0));
+        assert!((l - 53.24).abs() < 0.01 && (a - 80.09).abs() < 0.01 && (b - 67.20).abs() < 0.01);
+    }
+
+    #[test]
+    fn ciede2000_sharma_data() {
+        /* Pairs from the supplementary test data of Sharma, Wu and Dalal */
+        let pairs = [
+            ((50.0000, 2.6772, -79.7751), (50.0000, 0.0000, -82.7485), 2.0425),
+            ((50.0000, -1.3802, -84.2814), (50.0000, 0.0000, -82.7485), 1.0000),
+            ((50.0000, 0.0000, 0.0000), (50.0000, -1.0000, 2.0000), 2.3669),
+            ((50.0000, 2.4900, -0.0010), (50.0000, -2.4900, 0.0011), 7.2195),
+            ((50.0000, 2.5000, 0.0000), (73.0000, 25.0000, -18.0000), 27.1492),
+            ((60.2574, -34.0099, 36.2677), (60.4626, -34.1751, 39.4387), 1.2644),
+            ((22.7233, 20.0904, -46.6940), (23.0331, 14.9730, -42.5619), 2.0373),
+            ((2.0776, 0.0795, -1.1350), (0.9033, -0.0636, -0.5514), 0.9082),
+        ];
+
+        for (lab1, lab2, expected) in pairs {
+            assert_close(ciede2000(lab1, lab2), expected);
+            assert_close(ciede2000(lab2, lab1), expected);
+        }
+    }
+
+    #[test]
+    fn metrics_are_zero_on_equal_colors() {
+        for metric in [DistanceMetric::Rgb, DistanceMetric::Redmean, DistanceMetric::Cie76, DistanceMetric::Ciede2000] {
+            assert_close(metric.distance((12, 200, 99), (12, 200, 99)), 0.0);
+            assert!(metric.distance((0, 0, 0), (255, 255, 255)) > 0.0);
+        }
+    }
+}
@@ -0,0 +1,346 @@
+//! Adaptive palette quantization
+//!
+//! Builds the N colors which best represent a set of pixels by:
+//! + Median cut: split the box of colors with the widest channel at its median
+//! + K-means: refine the median cut colors as the mean of their closest pixels
+//!
+//! The palette can then be restricted to the closest Terminal colors.
+
+use crate::metric::DistanceMetric;
+use crate::theme::Theme;
+use crate::{TermColor, TermPalette};
+use std::collections::HashMap;
+
+/// Colors along the number of pixels of each one
+type Histogram = Vec<((u8, u8, u8), u32)>;
+
+/// Method used to build an adaptive palette
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum QuantizeMethod {
+    /// Median cut
+    #[default]
+    MedianCut,
+    /// K-means seeded by the median cut colors
+    KMeans,
+}
+
+/// Builder of adaptive palettes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Quantizer {
+    colors: usize,
+    method: QuantizeMethod,
+    iterations: usize,
+    terminal: bool,
+}
+
+impl Quantizer {
+    /// Create a median cut quantizer of at most `colors` colors
+    pub fn new(colors: usize) -> Self {
+        Self { colors: colors.clamp(1, 256), method: QuantizeMethod::MedianCut, iterations: 16, terminal: false }
+    }
+
+    /// Set the method used to build the palette
+    pub fn method(&self, method: QuantizeMethod) -> Self {
+        Self { method, .. *self }
+    }
+
+    /// Set the maximum number of k-means iterations
+    pub fn iterations(&self, iterations: usize) -> Self {
+        Self { iterations, .. *self }
+    }
+
+    /// Restrict the palette to Terminal colors, see [`AdaptivePalette::restrict`]
+    pub fn terminal(&self) -> Self {
+        Self { terminal: true, .. *self }
+    }
+
+    /// Get the maximum number of colors
+    pub fn colors(&self) -> usize {
+        self.colors
+    }
+
+    /// Check if the palette is restricted to Terminal colors
+    pub fn is_terminal(&self) -> bool {
+        self.terminal
+    }
+
+    /// Build the palette representing `pixels`, empty when there are no pixels
+    pub fn quantize<I: IntoIterator<Item = (u8, u8, u8)>>(&self, pixels: I) -> AdaptivePalette {
+        let histogram = histogram(pixels);
+
+        let colors = match self.method {
+            QuantizeMethod::MedianCut => median_cut(&histogram, self.colors),
+            QuantizeMethod::KMeans => kmeans(&histogram, &median_cut(&histogram, self.colors), self.iterations),
+        };
+
+        AdaptivePalette { colors, indices: None }
+    }
+}
+
+/// Palette built for a particular image
+///
+/// When restricted to Terminal colors each color keeps its index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdaptivePalette {
+    colors: Vec<(u8, u8, u8)>,
+    indices: Option<Vec<u8>>,
+}
+
+impl AdaptivePalette {
+    /// Get the RGB value of the colors
+    pub fn colors(&self) -> &[(u8, u8, u8)] {
+        &self.colors
+    }
+
+    /// Get the Terminal color index of the colors, `None` if not restricted
+    pub fn indices(&self) -> Option<&[u8]> {
+        self.indices.as_deref()
+    }
+
+    /// Get the number of colors
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    /// Check if the palette has no colors
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    /// Restrict the colors to their closest color on a Terminal palette,
+    /// merging the ones sharing the same Terminal color
+    pub fn restrict(&self, theme: &Theme, palette: TermPalette, metric: DistanceMetric) -> Self {
+        let mut indices: Vec<u8> = vec![];
+        for &(r, g, b) in self.colors.iter() {
+            let index = theme.closest(r, g, b, palette, metric).index;
+            if !indices.contains(&index) {
+                indices.push(index);
+            }
+        }
+
+        Self {
+            colors: indices.iter().map(|&index| theme.color(index)).collect(),
+            indices: Some(indices),
+        }
+    }
+
+    /// Get the position of the closest color according to `metric`
+    pub fn position(&self, r: u8, g: u8, b: u8, metric: DistanceMetric) -> usize {
+        let mut min = f64::INFINITY;
+        let mut position = 0;
+
+        for (i, &color) in self.colors.iter().enumerate() {
+            let distance = metric.distance((r, g, b), color);
+            if distance < min {
+                min = distance;
+                position = i;
+            }
+        }
+
+        position
+    }
+
+    /// Create a TermColor from 24bit RGB color by finding the closest color according to `metric`,
+    /// whose index is the Terminal color index if restricted or the position otherwise
+    pub fn closest(&self, r: u8, g: u8, b: u8, metric: DistanceMetric) -> TermColor {
+        let position = self.position(r, g, b, metric);
+        let index = match &self.indices {
+            Some(indices) => indices[position],
+            None => position as u8,
+        };
+
+        TermColor { r, g, b, index }
+    }
+}
+
+/// Count the pixels of each color
+fn histogram<I: IntoIterator<Item = (u8, u8, u8)>>(pixels: I) -> Histogram {
+    let mut counts: HashMap<(u8, u8, u8), u32> = HashMap::new();
+    for pixel in pixels {
+        *counts.entry(pixel).or_insert(0) += 1;
+    }
+
+    /* Sorted so the result does not depend on the hashing */
+    let mut histogram: Histogram = counts.into_iter().collect();
+    histogram.sort_unstable();
+    histogram
+}
+
+/// Get a channel of a color, 0 red, 1 green and 2 blue
+fn channel(color: (u8, u8, u8), c: usize) -> u8 {
+    match c {
+        0 => color.0,
+        1 => color.1,
+        _ => color.2,
+    }
+}
+
+/// Get the widest channel of a box of colors and its range
+fn widest_channel(colors: &[((u8, u8, u8), u32)]) -> (usize, u8) {
+    (0..3).map(|c| {
+        let min = colors.iter().map(|&(color, _)| channel(color, c)).min().unwrap_or(0);
+        let max = colors.iter().map(|&(color, _)| channel(color, c)).max().unwrap_or(0);
+        (c, max - min)
+    })
+    .fold((0, 0), |widest, current| if current.1 > widest.1 { current } else { widest })
+}
+
+/// Get the mean color weighted by the counts
+fn mean(colors: &[((u8, u8, u8), u32)]) -> (u8, u8, u8) {
+    let mut sums = [0u64; 3];
+    let mut total = 0u64;
+    for &(color, count) in colors {
+        for (c, sum) in sums.iter_mut().enumerate() {
+            *sum += channel(color, c) as u64 * count as u64;
+        }
+        total += count as u64;
+    }
+    let total = total.max(1);
+    let mean = |sum: u64| ((sum + total / 2) / total) as u8;
+
+    (mean(sums[0]), mean(sums[1]), mean(sums[2]))
+}
+
+/// Split the color histogram in at most `n` boxes, returning the mean of each one
+fn median_cut(histogram: &[((u8, u8, u8), u32)], n: usize) -> Vec<(u8, u8, u8)> {
+    if histogram.is_empty() {
+        return vec![];
+    }
+    let mut boxes: Vec<Histogram> = vec![histogram.to_vec()];
+
+    while boxes.len() < n {
+        /* Split the box with the widest range, a single color box can not be split */
+        let (i, (c, range)) = boxes.iter()
+            .map(|colors| widest_channel(colors))
+            .enumerate()
+            .fold((0, (0, 0)), |widest, current| if current.1.1 > widest.1.1 { current } else { widest });
+        if range == 0 {
+            break;
+        }
+
+        let mut colors = boxes.swap_remove(i);
+        colors.sort_unstable_by_key(|&(color, _)| channel(color, c));
+
+        /* Median of the pixels, keeping at least a color on each side */
+        let total: u64 = colors.iter().map(|&(_, count)| count as u64).sum();
+        let mut accumulated = 0;
+        let mut split = 1;
+        for (j, &(_, count)) in colors.iter().enumerate() {
+            accumulated += count as u64;
+            if accumulated * 2 >= total {
+                split = j + 1;
+                break;
+            }
+        }
+        let split = split.clamp(1, colors.len() - 1);
+
+        let upper = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    let mut colors: Vec<(u8, u8, u8)> = boxes.iter().map(|colors| mean(colors)).collect();
+    colors.sort_unstable();
+    colors.dedup();
+    colors
+}
+
+/// Refine `centroids` as the mean of their closest colors of the histogram
+fn kmeans(histogram: &[((u8, u8, u8), u32)], centroids: &[(u8, u8, u8)], iterations: usize) -> Vec<(u8, u8, u8)> {
+    let mut centroids = centroids.to_vec();
+    if centroids.is_empty() {
+        return centroids;
+    }
+
+    for _ in 0..iterations {
+        let palette = AdaptivePalette { colors: centroids.clone(), indices: None };
+        let mut clusters: Vec<Histogram> = vec![vec![]; centroids.len()];
+        for &(color, count) in histogram {
+            clusters[palette.position(color.0, color.1, color.2, DistanceMetric::Rgb)].push((color, count));
+        }
+
+        /* Empty clusters keep their centroid */
+        let updated: Vec<(u8, u8, u8)> = clusters.iter().zip(centroids.iter())
+            .map(|(cluster, &centroid)| if cluster.is_empty() { centroid } else { mean(cluster) })
+            .collect();
+
+        if updated == centroids {
+            break;
+        }
+        centroids = updated;
+    }
+
+    centroids.sort_unstable();
+    centroids.dedup();
+    centroids
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pixels of four clusters around red, green, blue and gray, the red one `weight` times bigger
+    fn clustered_pixels(weight: usize) -> Vec<(u8, u8, u8)> {
+        let mut pixels = vec![];
+        for i in 0..10u8 {
+            for _ in 0..weight {
+                pixels.push((200 + i, 10 + i, 20));
+            }
+            pixels.push((10, 180 + i, 30 + i));
+            pixels.push((i, 20, 220 - i));
+            pixels.push((120 + i, 120 + i, 120 + i));
+        }
+        pixels
+    }
+
+    fn distance_to_palette(palette: &AdaptivePalette, pixel: (u8, u8, u8)) -> f64 {
+        let position = palette.position(pixel.0, pixel.1, pixel.2, DistanceMetric::Rgb);
+        DistanceMetric::Rgb.distance(pixel, palette.colors()[position])
+    }
+
+    #[test]
+    fn median_cut_clusters() {
+        let pixels = clustered_pixels(1);
+        let palette = Quantizer::new(4).quantize(pixels.iter().copied());
+
+        assert_eq!(palette.len(), 4);
+        for &pixel in pixels.iter() {
+            assert!(distance_to_palette(&palette, pixel) < 20.0, "{:?} {:?}", pixel, palette);
+        }
+
+        /* Fewer distinct colors than asked */
+        let palette = Quantizer::new(16).quantize([(1, 2, 3), (1, 2, 3), (200, 100, 0)]);
+        assert_eq!(palette.colors(), [(1, 2, 3), (200, 100, 0)]);
+        assert!(Quantizer::new(8).quantize([]).is_empty());
+    }
+
+    #[test]
+    fn kmeans_improves_median_cut() {
+        /* The median of the pixels falls inside the big cluster */
+        let pixels = clustered_pixels(4);
+        let error = |palette: &AdaptivePalette| -> f64 {
+            pixels.iter().map(|&pixel| distance_to_palette(palette, pixel).powi(2)).sum()
+        };
+
+        let median_cut = Quantizer::new(3).quantize(pixels.iter().copied());
+        let kmeans = Quantizer::new(3).method(QuantizeMethod::KMeans).quantize(pixels.iter().copied());
+
+        assert_eq!(kmeans.len(), 3);
+        assert!(error(&kmeans) <= error(&median_cut));
+    }
+
+    #[test]
+    fn restrict_to_terminal_colors() {
+        let pixels = clustered_pixels(1);
+        let palette = Quantizer::new(4).quantize(pixels.iter().copied())
+            .restrict(&Theme::new(), TermPalette::Ansi16, DistanceMetric::Rgb);
+
+        let indices = palette.indices().unwrap();
+        assert!(indices.iter().all(|&index| index < 16));
+        assert_eq!(palette.colors()[0], Theme::new().color(indices[0]));
+
+        /* Pure red maps to the bright red of the palette */
+        assert_eq!(palette.closest(255, 0, 0, DistanceMetric::Rgb).index, 9);
+    }
+}
@@ -0,0 +1,424 @@
+//! Terminal color themes
+//!
+//! A theme gives the real RGB value of each of the 256 Terminal colors,
+//! usually redefining the first 16 colors, loaded from:
+//! + GIMP palettes (`.gpl`)
+//! + Lists of hexadecimal colors
+//! + Xresources (`*.color0: #282828`)
+//! + Alacritty colors, either YAML or TOML (`[colors.normal]`)
+
+use crate::metric::{self, DistanceMetric, Lab};
+use crate::{TermColor, TermPalette, TERM256_COLOR};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Names of the basic colors ordered by index, as used by Alacritty
+const COLOR_NAMES: [&str; 8] = ["black", "red", "green", "yellow", "blue", "magenta", "cyan", "white"];
+
+/// Colors given by a theme file, as index and RGB value
+type Assignments = Vec<(u8, (u8, u8, u8))>;
+
+/// Error loading a theme
+#[derive(Debug)]
+pub enum ThemeError {
+    /// The file could not be read
+    ReadError(std::io::Error),
+    /// A line could not be parsed, with its number starting at one
+    ParseError(usize, String),
+    /// No color is defined
+    Empty,
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ReadError(e) => write!(f, "Error reading palette: \"{}\"", e),
+            Self::ParseError(line, message) => write!(f, "Error parsing palette at line {}: {}", line, message),
+            Self::Empty => write!(f, "Error parsing palette: no colors found"),
+        }
+    }
+}
+
+impl std::error::Error for ThemeError {}
+
+/// RGB values of the 256 Terminal colors
+///
+/// The default theme uses the xterm colors of [`TERM256_COLOR`].
+#[derive(Clone)]
+pub struct Theme {
+    colors: [(u8, u8, u8); 256],
+    /// CIELAB value of each color
+    labs: [Lab; 256],
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::from_colors(&[])
+    }
+}
+
+/// Themes are equal when their colors are, the CIELAB values follow from them
+impl PartialEq for Theme {
+    fn eq(&self, other: &Self) -> bool {
+        self.colors == other.colors
+    }
+}
+
+impl Eq for Theme {}
+
+impl fmt::Debug for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Theme")
+            .field("ansi16", &&self.colors[..16])
+            .finish_non_exhaustive()
+    }
+}
+
+impl Theme {
+    /// Create the theme of the xterm colors
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a theme redefining the first colors, keeping the xterm value of the rest
+    ///
+    /// Colors after the 256th are ignored.
+    pub fn from_colors(colors: &[(u8, u8, u8)]) -> Self {
+        let mut table = TERM256_COLOR;
+        for (entry, &color) in table.iter_mut().zip(colors) {
+            *entry = color;
+        }
+
+        Self { colors: table, labs: table.map(metric::rgb_to_lab) }
+    }
+
+    /// Load a theme from a file, see [`Theme::parse`] for the formats supported
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ThemeError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text),
+            Err(e) => Err(ThemeError::ReadError(e)),
+        }
+    }
+
+    /// Parse a theme given as a GIMP palette, a list of hexadecimal colors,
+    /// Xresources or Alacritty colors
+    ///
+    /// + GIMP palettes start with `GIMP Palette` and give a color per line as decimal `R G B`.
+    /// + Lists give colors by index as `#rrggbb`, `rrggbb` or `0xrrggbb`,
+    ///   separated by spaces, commas or lines, with `#` comments on their own line.
+    /// + Xresources give colors as `*.colorN: value`, with `!` comments,
+    ///   the value either hexadecimal or `rgb:rr/gg/bb`.
+    /// + Alacritty gives the `normal` and `bright` colors by name,
+    ///   as `black: '#1d1f21'` on YAML or `black = "#1d1f21"` on TOML.
+    ///
+    /// Colors not given keep their xterm value.
+    pub fn parse(text: &str) -> Result<Self, ThemeError> {
+        let is_gpl = text.lines()
+            .find(|line| !line.trim().is_empty())
+            .is_some_and(|line| line.trim().starts_with("GIMP Palette"));
+
+        let assignments =
+        if is_gpl {
+            parse_gpl(text)?
+        } else {
+            parse_entries(text)?
+        };
+
+        if assignments.is_empty() {
+            return Err(ThemeError::Empty);
+        }
+
+        let mut table = TERM256_COLOR;
+        for (index, color) in assignments {
+            table[index as usize] = color;
+        }
+
+        Ok(Self { colors: table, labs: table.map(metric::rgb_to_lab) })
+    }
+
+    /// Get the RGB value of a color index
+    pub fn color(&self, index: u8) -> (u8, u8, u8) {
+        self.colors[index as usize]
+    }
+
+    /// Get the RGB value of the palette colors ordered by index
+    pub fn colors(&self, palette: TermPalette) -> &[(u8, u8, u8)] {
+        &self.colors[..palette.size()]
+    }
+
+    /// Find the closest color of the palette to a 24bit RGB color according to `metric`
+    pub fn closest(&self, r: u8, g: u8, b: u8, palette: TermPalette, metric: DistanceMetric) -> TermColor {
+        let rgb = (r, g, b);
+        let colors = self.colors(palette);
+
+        let index = match metric {
+            DistanceMetric::Rgb => argmin(colors.iter().map(|&color| metric::rgb_quadrance(rgb, color))),
+            DistanceMetric::Redmean => argmin(colors.iter().map(|&color| metric::redmean_quadrance(rgb, color))),
+            DistanceMetric::Cie76 | DistanceMetric::Ciede2000 => {
+                let lab = metric::rgb_to_lab(rgb);
+                let distance = if metric == DistanceMetric::Cie76 { metric::cie76 } else { metric::ciede2000 };
+                argmin(self.labs[..colors.len()].iter().map(|&color| distance(lab, color)))
+            },
+        };
+
+        TermColor { r, g, b, index }
+    }
+}
+
+/// Get the theme of the xterm colors, created once
+pub(crate) fn xterm_theme() -> &'static Theme {
+    static XTERM: OnceLock<Theme> = OnceLock::new();
+
+    XTERM.get_or_init(Theme::default)
+}
+
+/// Get the index of the minimum distance, the first one on ties
+fn argmin(distances: impl Iterator<Item = f64>) -> u8 {
+    let mut min = f64::INFINITY;
+    let mut index = 0;
+
+    for (i, distance) in distances.enumerate() {
+        if distance < min {
+            min = distance;
+            index = i as u8;
+        }
+    }
+
+    index
+}
+
+/// Get the colors of a GIMP palette by index
+fn parse_gpl(text: &str) -> Result<Assignments, ThemeError> {
+    let mut assignments = vec![];
+
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("GIMP Palette") ||
+           line.starts_with("Name:") || line.starts_with("Columns:") {
+            continue;
+        }
+
+        let channels: Vec<Option<u8>> = line.split_whitespace().take(3).map(|value| value.parse().ok()).collect();
+        match channels[..] {
+            [Some(r), Some(g), Some(b)] => push_color(&mut assignments, n, (r, g, b))?,
+            _ => return Err(ThemeError::ParseError(n + 1, format!("invalid color \"{}\"", line))),
+        }
+    }
+
+    Ok(assignments)
+}
+
+/// Get the colors of a list, Xresources or Alacritty colors by index
+fn parse_entries(text: &str) -> Result<Assignments, ThemeError> {
+    let mut assignments = vec![];
+    /* Index of the first color of the current Alacritty section */
+    let mut section: Option<u8> = None;
+
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        let first = line.split(|c: char| c.is_whitespace() || c == ',').next().unwrap_or("");
+        if line.is_empty() || line.starts_with('!') || (line.starts_with('#') && parse_color(first).is_none()) {
+            continue;
+        }
+
+        /* TOML tables */
+        if line.starts_with('[') {
+            section = match line.trim_matches(['[', ']']).trim() {
+                "colors.normal" => Some(0),
+                "colors.bright" => Some(8),
+                _ => None,
+            };
+            continue;
+        }
+
+        if let Some(split) = line.find([':', '=']) {
+            let key = line[..split].trim().trim_matches(['"', '\'']);
+            let value = line[split + 1..].trim();
+
+            /* YAML mappings */
+            if value.is_empty() {
+                section = match key {
+                    "normal" => Some(0),
+                    "bright" => Some(8),
+                    _ => None,
+                };
+                continue;
+            }
+
+            let index =
+            if let Some(index) = xresources_index(key) {
+                Some(index)
+            } else {
+                section.and_then(|base| {
+                    COLOR_NAMES.iter().position(|&name| name == key).map(|offset| base + offset as u8)
+                })
+            };
+
+            /* Other keys, as foreground or cursor colors, are ignored */
+            if let Some(index) = index {
+                match parse_color(value) {
+                    Some(color) => assignments.push((index, color)),
+                    None => return Err(ThemeError::ParseError(n + 1, format!("invalid color \"{}\"", value))),
+                }
+            }
+            continue;
+        }
+
+        for token in line.split(|c: char| c.is_whitespace() || c == ',').filter(|token| !token.is_empty()) {
+            match parse_color(token) {
+                Some(color) => push_color(&mut assignments, n, color)?,
+                None => return Err(ThemeError::ParseError(n + 1, format!("invalid color \"{}\"", token))),
+            }
+        }
+    }
+
+    Ok(assignments)
+}
+
+/// Append a color at the next index, failing after 256 colors
+fn push_color(assignments: &mut Assignments, line: usize, color: (u8, u8, u8)) -> Result<(), ThemeError> {
+    if assignments.len() == 256 {
+        return Err(ThemeError::ParseError(line + 1, String::from("more than 256 colors")));
+    }
+    assignments.push((assignments.len() as u8, color));
+
+    Ok(())
+}
+
+/// Get the index of a Xresources key as `*.color12` or `URxvt*color12`
+fn xresources_index(key: &str) -> Option<u8> {
+    let start = key.rfind("color")?;
+    let digits = &key[start + "color".len()..];
+
+    if start > 0 && !key[..start].ends_with(['*', '.']) {
+        return None;
+    }
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// Parse a color as `#rrggbb`, `#rgb`, `rrggbb`, `0xrrggbb` or `rgb:rr/gg/bb`, optionally quoted
+fn parse_color(value: &str) -> Option<(u8, u8, u8)> {
+    let value = value.split_whitespace().next()?.trim_matches(['"', '\'']);
+
+    if let Some(channels) = value.strip_prefix("rgb:") {
+        /* X11 channels of 1 to 4 hexadecimal digits, scaled to 8 bits */
+        let channels: Vec<Option<u8>> = channels.split('/').map(|channel| {
+            if channel.is_empty() || channel.len() > 4 {
+                return None;
+            }
+            let max = (1u32 << (4 * channel.len())) - 1;
+            u32::from_str_radix(channel, 16).ok().map(|v| ((v * 255 + max / 2) / max) as u8)
+        }).collect();
+
+        return match channels[..] {
+            [Some(r), Some(g), Some(b)] => Some((r, g, b)),
+            _ => None,
+        };
+    }
+
+    let hex = value.strip_prefix('#')
+        .or_else(|| value.strip_prefix("0x"))
+        .unwrap_or(value);
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let channel = |i: usize, len: usize| u8::from_str_radix(&hex[i * len..(i + 1) * len], 16).ok();
+    match hex.len() {
+        6 => Some((channel(0, 2)?, channel(1, 2)?, channel(2, 2)?)),
+        3 => Some((channel(0, 1)? * 17, channel(1, 1)? * 17, channel(2, 1)? * 17)),
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_colors() {
+        assert_eq!(parse_color("#1d1f21"), Some((0x1d, 0x1f, 0x21)));
+        assert_eq!(parse_color("'0xFF8000'"), Some((255, 128, 0)));
+        assert_eq!(parse_color("\"fff\""), Some((255, 255, 255)));
+        assert_eq!(parse_color("rgb:ff/80/0"), Some((255, 128, 0)));
+        assert_eq!(parse_color("rgb:ffff/0000/8080"), Some((255, 0, 128)));
+        assert_eq!(parse_color("#12345"), None);
+        assert_eq!(parse_color("red"), None);
+    }
+
+    #[test]
+    fn parse_gimp_palette() {
+        let theme = Theme::parse("GIMP Palette\nName: Solarized\nColumns: 8\n# comment\n  7  54  66\tbase02\n220  50  47 red\n").unwrap();
+
+        assert_eq!(theme.color(0), (7, 54, 66));
+        assert_eq!(theme.color(1), (220, 50, 47));
+        assert_eq!(theme.color(2), TERM256_COLOR[2]);
+
+        assert!(matches!(Theme::parse("GIMP Palette\n1 2\n"), Err(ThemeError::ParseError(2, _))));
+    }
+
+    #[test]
+    fn parse_hex_list() {
+        let theme = Theme::parse("# Gruvbox\n#282828, #cc241d\n98971a\n0xd79921 #458588\n").unwrap();
+
+        assert_eq!(theme.colors(TermPalette::Ansi8)[..5],
+                   [(0x28, 0x28, 0x28), (0xcc, 0x24, 0x1d), (0x98, 0x97, 0x1a), (0xd7, 0x99, 0x21), (0x45, 0x85, 0x88)]);
+        assert_eq!(theme.color(5), TERM256_COLOR[5]);
+
+        assert!(matches!(Theme::parse("#282828\nnot a color\n"), Err(ThemeError::ParseError(2, _))));
+        assert!(matches!(Theme::parse("# only comments\n"), Err(ThemeError::Empty)));
+    }
+
+    #[test]
+    fn parse_xresources() {
+        let text = "! Dracula\n*.foreground: #f8f8f2\n*.color0: #21222c\nURxvt*color9 : rgb:ff/6e/6e\n*color15:\t#ffffff\n";
+        let theme = Theme::parse(text).unwrap();
+
+        assert_eq!(theme.color(0), (0x21, 0x22, 0x2c));
+        assert_eq!(theme.color(9), (0xff, 0x6e, 0x6e));
+        assert_eq!(theme.color(15), (255, 255, 255));
+        assert_eq!(theme.color(1), TERM256_COLOR[1]);
+    }
+
+    #[test]
+    fn parse_alacritty() {
+        let yaml = concat!(
+            "colors:\n",
+            "  primary:\n",
+            "    background: '#1d1f21'\n",
+            "  normal:\n",
+            "    black:   '#1d1f21'\n",
+            "    red:     '#cc6666'\n",
+            "  bright:\n",
+            "    red:     '#d54e53' # comment\n",
+            "  dim:\n",
+            "    red:     '#000000'\n",
+        );
+        let theme = Theme::parse(yaml).unwrap();
+        assert_eq!(theme.color(0), (0x1d, 0x1f, 0x21));
+        assert_eq!(theme.color(1), (0xcc, 0x66, 0x66));
+        assert_eq!(theme.color(9), (0xd5, 0x4e, 0x53));
+
+        let toml = "[colors.primary]\nbackground = \"#000000\"\n\n[colors.normal]\nblue = \"0x81a2be\"\n\n[colors.bright]\nwhite = \"#ffffff\"\n";
+        let theme = Theme::parse(toml).unwrap();
+        assert_eq!(theme.color(4), (0x81, 0xa2, 0xbe));
+        assert_eq!(theme.color(15), (255, 255, 255));
+        assert_eq!(theme.color(0), TERM256_COLOR[0]);
+    }
+
+    #[test]
+    fn closest_uses_theme_colors() {
+        /* Solarized blue is the closest to the theme blue, not the xterm one */
+        let theme = Theme::from_colors(&[(0, 43, 54), (220, 50, 47), (133, 153, 0), (181, 137, 0), (38, 139, 210)]);
+
+        assert_eq!(theme.closest(40, 140, 200, TermPalette::Ansi8, DistanceMetric::Rgb).index, 4);
+        assert_eq!(TermColor::closest(40, 140, 200, TermPalette::Ansi8, DistanceMetric::Rgb).index, 6);
+        assert_eq!(Theme::new(), *xterm_theme());
+    }
+}